@@ -0,0 +1,110 @@
+use crate::math::{Vec3, Point3, Ray, Transform};
+
+/// Axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Aabb::empty()
+    }
+}
+
+impl Aabb {
+    pub fn new(min: Point3, max: Point3) -> Self {
+        Aabb { min, max }
+    }
+
+    /// A box containing nothing; growing it by any box yields that box.
+    pub fn empty() -> Self {
+        Aabb {
+            min: Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Aabb { min: Vec3::min(a, b), max: Vec3::max(a, b) }
+    }
+
+    pub fn surrounding(a: Aabb, b: Aabb) -> Self {
+        Aabb { min: Vec3::min(a.min, b.min), max: Vec3::max(a.max, b.max) }
+    }
+
+    pub fn grow(self, p: Point3) -> Self {
+        Aabb { min: Vec3::min(self.min, p), max: Vec3::max(self.max, p) }
+    }
+
+    pub fn corners(&self) -> [Point3; 8] {
+        std::array::from_fn(|i| Point3::new(
+            if i & 1 == 0 { self.min.x } else { self.max.x },
+            if i & 2 == 0 { self.min.y } else { self.max.y },
+            if i & 4 == 0 { self.min.z } else { self.max.z },
+        ))
+    }
+
+    /// Box around this one after `transform`.
+    pub fn transformed(&self, transform: &Transform) -> Aabb {
+        self.corners().iter().fold(Aabb::empty(), |acc, &p| acc.grow(transform.transform_point(p)))
+    }
+
+    /// Grown by `margin` on every side.
+    pub fn padded(self, margin: f32) -> Aabb {
+        let m = Vec3::new(margin, margin, margin);
+        Aabb { min: self.min - m, max: self.max + m }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let d = self.extent();
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> bool {
+        self.span(ray, t_min, t_max).is_some()
+    }
+
+    /// Part of `t_min..t_max` for which the ray is inside the box.
+    pub fn span(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<(f32, f32)> {
+        let inv_dir = Vec3::new(1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z);
+        self.span_inv(ray.origin, inv_dir, t_min, t_max)
+    }
+
+    /// Slab test with a precomputed reciprocal direction, so a traversal
+    /// only pays for the divisions once per ray.
+    pub(crate) fn hit_inv(&self, origin: Point3, inv_dir: Vec3, t_min: f32, t_max: f32) -> bool {
+        self.span_inv(origin, inv_dir, t_min, t_max).is_some()
+    }
+
+    fn span_inv(&self, origin: Point3, inv_dir: Vec3, mut t_min: f32, mut t_max: f32) -> Option<(f32, f32)> {
+        for axis in 0..3 {
+            let t0 = (self.min[axis] - origin[axis]) * inv_dir[axis];
+            let t1 = (self.max[axis] - origin[axis]) * inv_dir[axis];
+            let (t0, t1) = if inv_dir[axis] < 0.0 { (t1, t0) } else { (t0, t1) };
+            // Written so that a NaN (0 * inf on a slab boundary) keeps the old bound.
+            if t0 > t_min { t_min = t0; }
+            if t1 < t_max { t_max = t1; }
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
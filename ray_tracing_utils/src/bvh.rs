@@ -0,0 +1,348 @@
+use crate::math::{Vec3, Point3, Ray};
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, HittableList};
+
+const MAX_LEAF_SIZE: usize = 4;
+const SAH_BINS: usize = 12;
+// Cost of visiting a node, relative to intersecting one primitive.
+const TRAVERSAL_COST: f32 = 1.0;
+// Below this depth splits fall back to the median, which bounds the tree
+// depth (and the traversal stack) even for pathological inputs.
+const MAX_SAH_DEPTH: usize = 48;
+const STACK_SIZE: usize = 96;
+
+#[derive(Clone, Copy)]
+struct FlatNode {
+    bbox: Aabb,
+    // Leaf: first slot in `indices`. Inner: index of the second child; the
+    // first child always directly follows its parent.
+    offset: u32,
+    // Number of primitives in a leaf, 0 for inner nodes.
+    count: u32,
+    axis: u8,
+}
+
+/// Flattened bounding volume hierarchy over primitive boxes. Primitives are
+/// referred to by their position in the slice the tree was built from, so
+/// the same tree serves boxed hittables and indexed geometry alike.
+pub(crate) struct BvhTree {
+    nodes: Vec<FlatNode>,
+    indices: Vec<usize>,
+}
+
+impl BvhTree {
+    pub(crate) fn build(bounds: &[Aabb]) -> Self {
+        let centroids: Vec<Point3> = bounds.iter().map(|b| b.centroid()).collect();
+        let mut indices: Vec<usize> = (0..bounds.len()).collect();
+        let mut nodes = Vec::with_capacity(2 * bounds.len());
+        if !bounds.is_empty() {
+            build_recursive(bounds, &centroids, &mut indices, 0, 0, &mut nodes);
+        }
+        BvhTree { nodes, indices }
+    }
+
+    pub(crate) fn bounding_box(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| node.bbox)
+    }
+
+    /// Walks the tree front to back, calling `hit_primitive(index, t_max)`
+    /// for every primitive whose leaf the ray reaches, and keeps the closest hit.
+    pub(crate) fn hit<'a, F>(&self, ray: &Ray, t_min: f32, t_max: f32, mut hit_primitive: F) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, f32) -> Option<HitRecord<'a>>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let inv_dir = Vec3::new(1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z);
+        let dir_is_neg = [inv_dir.x < 0.0, inv_dir.y < 0.0, inv_dir.z < 0.0];
+
+        let mut closest_so_far = t_max;
+        let mut closest_rec: Option<HitRecord<'a>> = None;
+
+        let mut stack = [0usize; STACK_SIZE];
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+            if node.bbox.hit_inv(ray.origin, inv_dir, t_min, closest_so_far) {
+                if node.count > 0 {
+                    let start = node.offset as usize;
+                    let end = start + node.count as usize;
+                    for &prim in &self.indices[start..end] {
+                        if let Some(rec) = hit_primitive(prim, closest_so_far) {
+                            closest_so_far = rec.t;
+                            closest_rec = Some(rec);
+                        }
+                    }
+                } else {
+                    // Visit the child nearer to the ray origin first so that
+                    // `closest_so_far` prunes the farther one.
+                    let (near, far) = if dir_is_neg[node.axis as usize] {
+                        (node.offset as usize, current + 1)
+                    } else {
+                        (current + 1, node.offset as usize)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+
+        closest_rec
+    }
+
+    /// Calls `visit_primitive(index)` for every primitive whose leaf the ray
+    /// reaches between `t_min` and `t_max`, in no particular order, until it
+    /// returns `false`.
+    pub(crate) fn visit<F>(&self, ray: &Ray, t_min: f32, t_max: f32, mut visit_primitive: F)
+    where
+        F: FnMut(usize) -> bool,
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let inv_dir = Vec3::new(1.0 / ray.direction.x, 1.0 / ray.direction.y, 1.0 / ray.direction.z);
+        let mut stack = [0usize; STACK_SIZE];
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+            if node.bbox.hit_inv(ray.origin, inv_dir, t_min, t_max) {
+                if node.count > 0 {
+                    let start = node.offset as usize;
+                    let end = start + node.count as usize;
+                    for &prim in &self.indices[start..end] {
+                        if !visit_primitive(prim) {
+                            return;
+                        }
+                    }
+                } else {
+                    stack[stack_len] = node.offset as usize;
+                    stack_len += 1;
+                    current += 1;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+    }
+}
+
+fn build_recursive(
+    bounds: &[Aabb],
+    centroids: &[Point3],
+    indices: &mut [usize],
+    offset: usize,
+    depth: usize,
+    nodes: &mut Vec<FlatNode>,
+) -> usize {
+    let bbox = indices.iter().fold(Aabb::empty(), |acc, &i| Aabb::surrounding(acc, bounds[i]));
+    let node_idx = nodes.len();
+    nodes.push(FlatNode { bbox, offset: offset as u32, count: indices.len() as u32, axis: 0 });
+
+    let (axis, mid) = match split(bounds, centroids, indices, bbox, depth) {
+        Some(s) => s,
+        None => return node_idx,
+    };
+
+    let (left, right) = indices.split_at_mut(mid);
+    build_recursive(bounds, centroids, left, offset, depth + 1, nodes);
+    let second = build_recursive(bounds, centroids, right, offset + mid, depth + 1, nodes);
+
+    let node = &mut nodes[node_idx];
+    node.offset = second as u32;
+    node.count = 0;
+    node.axis = axis as u8;
+
+    node_idx
+}
+
+/// Partitions `indices` in place and returns the split axis and position,
+/// or `None` if the primitives are best kept together in a leaf.
+fn split(
+    bounds: &[Aabb],
+    centroids: &[Point3],
+    indices: &mut [usize],
+    bbox: Aabb,
+    depth: usize,
+) -> Option<(usize, usize)> {
+    let n = indices.len();
+    if n == 1 {
+        return None;
+    }
+
+    let centroid_box = indices.iter().fold(Aabb::empty(), |acc, &i| acc.grow(centroids[i]));
+    let extent = centroid_box.extent();
+    let widest = if extent.x > extent.y && extent.x > extent.z {
+        0
+    } else if extent.y > extent.z {
+        1
+    } else {
+        2
+    };
+
+    if extent[widest] <= 0.0 {
+        // All centroids coincide, so no plane can separate them.
+        return if n <= MAX_LEAF_SIZE { None } else { Some((widest, n / 2)) };
+    }
+
+    if depth >= MAX_SAH_DEPTH {
+        let mid = n / 2;
+        indices.select_nth_unstable_by(mid, |&a, &b| {
+            centroids[a][widest].total_cmp(&centroids[b][widest])
+        });
+        return Some((widest, mid));
+    }
+
+    let bin_of = |c: Point3, axis: usize| {
+        let scale = SAH_BINS as f32 / extent[axis];
+        (((c[axis] - centroid_box.min[axis]) * scale) as usize).min(SAH_BINS - 1)
+    };
+
+    let mut best_cost = f32::INFINITY;
+    let mut best_axis = widest;
+    let mut best_bin = SAH_BINS / 2;
+
+    for axis in 0..3 {
+        if extent[axis] <= 0.0 {
+            continue;
+        }
+
+        let mut bin_boxes = [Aabb::empty(); SAH_BINS];
+        let mut bin_counts = [0usize; SAH_BINS];
+        for &i in indices.iter() {
+            let b = bin_of(centroids[i], axis);
+            bin_boxes[b] = Aabb::surrounding(bin_boxes[b], bounds[i]);
+            bin_counts[b] += 1;
+        }
+
+        // right_*[b] describes everything in bins b.. SAH_BINS-1.
+        let mut right_area = [0.0f32; SAH_BINS];
+        let mut right_count = [0usize; SAH_BINS];
+        let mut acc = Aabb::empty();
+        let mut count = 0;
+        for b in (1..SAH_BINS).rev() {
+            acc = Aabb::surrounding(acc, bin_boxes[b]);
+            count += bin_counts[b];
+            right_area[b] = acc.surface_area();
+            right_count[b] = count;
+        }
+
+        let mut acc = Aabb::empty();
+        let mut count = 0;
+        for b in 1..SAH_BINS {
+            acc = Aabb::surrounding(acc, bin_boxes[b - 1]);
+            count += bin_counts[b - 1];
+            if count == 0 || right_count[b] == 0 {
+                continue;
+            }
+            let cost = count as f32 * acc.surface_area() + right_count[b] as f32 * right_area[b];
+            if cost < best_cost {
+                best_cost = cost;
+                best_axis = axis;
+                best_bin = b;
+            }
+        }
+    }
+
+    let parent_area = bbox.surface_area();
+    let split_cost = if parent_area > 0.0 {
+        TRAVERSAL_COST + best_cost / parent_area
+    } else {
+        TRAVERSAL_COST
+    };
+    if n <= MAX_LEAF_SIZE && n as f32 <= split_cost {
+        return None;
+    }
+
+    let mut mid = 0;
+    for k in 0..n {
+        if bin_of(centroids[indices[k]], best_axis) < best_bin {
+            indices.swap(k, mid);
+            mid += 1;
+        }
+    }
+    if mid == 0 || mid == n {
+        mid = n / 2;
+    }
+
+    Some((best_axis, mid))
+}
+
+/// Bounding volume hierarchy over the objects of a `HittableList`, built with
+/// the surface area heuristic. It reports the same hits as the list itself.
+/// Objects without a bounding box are kept aside and tested linearly.
+pub struct BvhNode {
+    tree: BvhTree,
+    hittables: Vec<Box<dyn Hittable>>,
+    unbounded: HittableList,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> Self {
+        let mut hittables = vec![];
+        let mut bounds = vec![];
+        let mut unbounded = HittableList::default();
+
+        for hittable in list.hittables {
+            match hittable.bounding_box() {
+                Some(bbox) => {
+                    bounds.push(bbox);
+                    hittables.push(hittable);
+                },
+                None => unbounded.hittables.push(hittable),
+            }
+        }
+
+        let tree = BvhTree::build(&bounds);
+
+        BvhNode { tree, hittables, unbounded }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let closest_rec = self.tree.hit(ray, t_min, t_max, |i, closest_so_far| {
+            self.hittables[i].hit(ray, t_min, closest_so_far)
+        });
+
+        let closest_so_far = closest_rec.as_ref().map_or(t_max, |rec| rec.t);
+        self.unbounded.hit(ray, t_min, closest_so_far).or(closest_rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        if !self.unbounded.hittables.is_empty() {
+            return None;
+        }
+        self.tree.bounding_box()
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f32, t_max: f32) -> f32 {
+        let mut transmittance = self.unbounded.transmittance(ray, t_min, t_max);
+        if transmittance > 0.0 {
+            self.tree.visit(ray, t_min, t_max, |i| {
+                transmittance *= self.hittables[i].transmittance(ray, t_min, t_max);
+                transmittance > 0.0
+            });
+        }
+        transmittance
+    }
+}
@@ -1,4 +1,7 @@
+use rand::Rng;
+
 use crate::math::{Vec3, Point3, Ray};
+use crate::sampling::with_rng;
 
 pub struct Camera {
     origin: Point3,
@@ -7,8 +10,10 @@ pub struct Camera {
     vertical: Vec3,
     u: Vec3,
     v: Vec3,
-    w: Vec3,
     lens_radius: f32,
+    // Shutter open and close times.
+    time0: f32,
+    time1: f32,
 }
 
 impl Camera {
@@ -32,18 +37,52 @@ impl Camera {
             lower_left_corner,
             horizontal,
             vertical,
-            u, v, w,
+            u, v,
             lens_radius,
+            time0: 0.0,
+            time1: 0.0,
         }
     }
 
+    /// Keeps the shutter open from `open` to `close`, stamping every ray with
+    /// a random time in between so moving objects blur.
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Self {
+        self.time0 = open;
+        self.time1 = close;
+        self
+    }
+
+    pub fn shutter(&self) -> (f32, f32) {
+        (self.time0, self.time1)
+    }
+
+    /// Width over height of the image plane.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.horizontal.length() / self.vertical.length()
+    }
+
+    /// Widens or narrows the image plane around its center, keeping the
+    /// vertical field of view, so the camera matches a new image size.
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        let horizontal = self.horizontal * (aspect_ratio / self.aspect_ratio());
+        self.lower_left_corner = self.lower_left_corner + self.horizontal / 2.0 - horizontal / 2.0;
+        self.horizontal = horizontal;
+    }
+
     pub fn get_ray(&self, s: f32, t: f32) -> Ray {
         let rd = self.lens_radius * Vec3::random_in_unit_disk();
         let offset = self.u * rd.x + self.v * rd.y;
 
+        let time = if self.time1 > self.time0 {
+            with_rng(|rng| rng.gen_range(self.time0..self.time1))
+        } else {
+            self.time0
+        };
+
         Ray {
             origin: self.origin + offset,
-            direction: self.lower_left_corner + self.horizontal*s + self.vertical*t - self.origin - offset
+            direction: self.lower_left_corner + self.horizontal*s + self.vertical*t - self.origin - offset,
+            time,
         }
     }
 }
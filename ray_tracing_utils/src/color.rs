@@ -1,4 +1,5 @@
 use crate::math::{Color, clamp};
+use crate::image::Pixel;
 
 pub fn write_pixel(color: Color) {
     let r = (clamp(color.x, 0.0, 0.999) * 256.0) as i32;
@@ -8,9 +9,25 @@ pub fn write_pixel(color: Color) {
 }
 
 pub fn write_pixel_sample(color: Color, samples_per_pixel: i32) {
+    let px = pixel_sample(color, samples_per_pixel);
+    println!("{} {} {}", px.r, px.g, px.b);
+}
+
+/// Averages a sum of `samples_per_pixel` samples and gamma-corrects it (gamma 2).
+pub fn pixel_sample(color: Color, samples_per_pixel: i32) -> Pixel {
     let s = 1.0 / samples_per_pixel as f32;
-    let r = (clamp((color.x * s).sqrt(), 0.0, 0.999) * 256.0) as i32;
-    let g = (clamp((color.y * s).sqrt(), 0.0, 0.999) * 256.0) as i32;
-    let b = (clamp((color.z * s).sqrt(), 0.0, 0.999) * 256.0) as i32;
-    println!("{} {} {}", r, g, b);
+    let r = (clamp((color.x * s).sqrt(), 0.0, 0.999) * 256.0) as u8;
+    let g = (clamp((color.y * s).sqrt(), 0.0, 0.999) * 256.0) as u8;
+    let b = (clamp((color.z * s).sqrt(), 0.0, 0.999) * 256.0) as u8;
+    Pixel { r, g, b, a: 0xff }
+}
+
+/// Undoes the gamma 2 encoding of `pixel_sample`.
+pub(crate) fn decode_gamma(color: Color) -> Color {
+    color * color
+}
+
+/// Relative luminance of a linear Rec. 709 color.
+pub fn luminance(color: Color) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
 }
@@ -0,0 +1,189 @@
+use std::f32::consts::PI;
+use std::path::Path;
+
+use crate::math::{Vec3, Color, clamp};
+use crate::color::luminance;
+use crate::image::Image;
+use crate::hdr::{HdrImage, HdrError};
+use crate::sampling::{self, Distribution2D};
+
+/// Radiance arriving from infinitely far away, looked up by the renderer
+/// for every ray that leaves the scene.
+pub trait Environment: Send + Sync {
+    /// Color seen along `direction`, which need not be normalized.
+    fn color(&self, direction: Vec3) -> Color;
+
+    /// Picks a direction toward the environment, favoring bright parts.
+    /// Returns the unit direction, the color seen along it and the solid
+    /// angle density it was picked with. `None` if the environment does not
+    /// support importance sampling; it is then only found by scattered rays.
+    fn sample(&self) -> Option<(Vec3, Color, f32)> {
+        None
+    }
+
+    /// Solid angle density with which `sample` picks `direction`.
+    fn pdf(&self, _direction: Vec3) -> f32 {
+        0.0
+    }
+}
+
+/// Same color in every direction.
+#[derive(Default, Clone)]
+pub struct UniformEnvironment {
+    pub color: Color,
+}
+
+impl Environment for UniformEnvironment {
+    fn color(&self, _direction: Vec3) -> Color {
+        self.color
+    }
+}
+
+/// Linear blend from `bottom` (straight down) to `top` (straight up).
+#[derive(Clone)]
+pub struct GradientEnvironment {
+    pub bottom: Color,
+    pub top: Color,
+}
+
+impl GradientEnvironment {
+    /// White-to-blue sky from the book.
+    pub fn sky() -> Self {
+        GradientEnvironment {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Default for GradientEnvironment {
+    fn default() -> Self {
+        GradientEnvironment::sky()
+    }
+}
+
+impl Environment for GradientEnvironment {
+    fn color(&self, direction: Vec3) -> Color {
+        let unit_direction = direction.normalized();
+        let t = 0.5 * (unit_direction.y + 1.0);
+        self.bottom * (1.0 - t) + self.top * t
+    }
+}
+
+/// Equirectangular (latitude-longitude) map of linear colors. The top row
+/// is straight up, and the horizontal center looks down -z.
+#[derive(Clone)]
+pub struct ImageEnvironment {
+    pub width: u32,
+    pub height: u32,
+    pub texels: Vec<Color>,
+    /// Linear scale applied to every lookup.
+    pub intensity: f32,
+}
+
+impl ImageEnvironment {
+    pub fn new(width: u32, height: u32, texels: Vec<Color>) -> Self {
+        assert!(width > 0 && height > 0, "environment map is empty");
+        assert_eq!(texels.len(), width as usize * height as usize);
+        ImageEnvironment { width, height, texels, intensity: 1.0 }
+    }
+
+    /// Decodes an 8-bit map the same way as `ImageTexture::from_image`.
+    pub fn from_image(image: &Image) -> Self {
+        ImageEnvironment::new(image.width, image.height, image.to_linear())
+    }
+
+    pub fn from_hdr(image: HdrImage) -> Self {
+        ImageEnvironment::new(image.width, image.height, image.data)
+    }
+
+    /// Texel coordinates in `[0, 1)` for a direction.
+    pub fn direction_to_uv(direction: Vec3) -> (f32, f32) {
+        let d = direction.normalized();
+        let u = 0.5 + d.x.atan2(-d.z) / (2.0 * PI);
+        let v = clamp(d.y, -1.0, 1.0).acos() / PI;
+        (u, v)
+    }
+
+    /// Inverse of `direction_to_uv`.
+    pub fn uv_to_direction(u: f32, v: f32) -> Vec3 {
+        let phi = (u - 0.5) * 2.0 * PI;
+        let theta = v * PI;
+        Vec3::new(theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos())
+    }
+
+    pub fn texel(&self, x: u32, y: u32) -> Color {
+        self.texels[y as usize * self.width as usize + x as usize]
+    }
+}
+
+impl Environment for ImageEnvironment {
+    fn color(&self, direction: Vec3) -> Color {
+        let (u, v) = ImageEnvironment::direction_to_uv(direction);
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        self.texel(x, y) * self.intensity
+    }
+}
+
+/// Equirectangular HDR map that the renderer samples directly as a light,
+/// picking directions in proportion to texel luminance. Small, very bright
+/// features such as a sun are then found by shadow rays instead of only by
+/// lucky scattered rays, which removes most of the fireflies they cause.
+#[derive(Clone)]
+pub struct HdrEnvironment {
+    map: ImageEnvironment,
+    distribution: Distribution2D,
+}
+
+impl HdrEnvironment {
+    pub fn new(map: ImageEnvironment) -> Self {
+        let (width, height) = (map.width as usize, map.height as usize);
+        let mut weights = Vec::with_capacity(width * height);
+        for y in 0..height {
+            // Rows near the poles cover less solid angle.
+            let sin_theta = ((y as f32 + 0.5) / height as f32 * PI).sin();
+            for x in 0..width {
+                weights.push(luminance(map.texels[y * width + x]) * sin_theta);
+            }
+        }
+        let distribution = Distribution2D::new(&weights, width, height);
+        HdrEnvironment { map, distribution }
+    }
+
+    /// Loads a Radiance `.hdr` file in equirectangular layout.
+    pub fn from_path<P: AsRef<Path>>(filepath: P) -> Result<Self, HdrError> {
+        Ok(HdrEnvironment::new(ImageEnvironment::from_hdr(HdrImage::from_path(filepath)?)))
+    }
+
+    pub fn map(&self) -> &ImageEnvironment {
+        &self.map
+    }
+
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.map.intensity = intensity;
+    }
+}
+
+impl Environment for HdrEnvironment {
+    fn color(&self, direction: Vec3) -> Color {
+        self.map.color(direction)
+    }
+
+    fn sample(&self) -> Option<(Vec3, Color, f32)> {
+        let ((u, v), _) = self.distribution.sample(sampling::random(), sampling::random());
+        let direction = ImageEnvironment::uv_to_direction(u, v);
+        // Evaluated from the direction so it agrees exactly with `pdf`.
+        Some((direction, self.color(direction), self.pdf(direction)))
+    }
+
+    fn pdf(&self, direction: Vec3) -> f32 {
+        let (u, v) = ImageEnvironment::direction_to_uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        // The map spans 2 pi by pi radians.
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+}
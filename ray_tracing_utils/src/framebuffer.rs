@@ -0,0 +1,168 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::math::{Color, clamp};
+use crate::image::{Image, Pixel, write_png_rgba};
+use crate::hdr::HdrImage;
+
+/// How linear colors are squeezed into the displayable range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMap {
+    /// Cut every channel off at 1.0.
+    #[default]
+    Clamp,
+    /// `c / (1 + c)` per channel, which keeps detail in bright regions.
+    Reinhard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolveSettings {
+    /// Linear scale applied before tone mapping.
+    pub exposure: f32,
+    pub gamma: f32,
+    pub tone_map: ToneMap,
+}
+
+impl Default for ResolveSettings {
+    fn default() -> Self {
+        ResolveSettings { exposure: 1.0, gamma: 2.0, tone_map: ToneMap::Clamp }
+    }
+}
+
+impl ResolveSettings {
+    /// Maps one linear channel value into `[0, 1]`.
+    pub fn apply(&self, c: f32) -> f32 {
+        let c = (c * self.exposure).max(0.0);
+        let c = match self.tone_map {
+            ToneMap::Clamp => c,
+            ToneMap::Reinhard => c / (1.0 + c),
+        };
+        let c = if self.gamma == 1.0 { c } else { c.powf(1.0 / self.gamma) };
+        clamp(c, 0.0, 1.0)
+    }
+}
+
+/// Linear, floating-point framebuffer that accumulates radiance samples.
+/// Each pixel keeps the sum of its samples and how many there were, so
+/// further passes can be added at any time before resolving to an `Image`.
+#[derive(Debug, Clone, Default)]
+pub struct Framebuffer {
+    pub width: u32,
+    pub height: u32,
+    sums: Vec<Color>,
+    counts: Vec<u32>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = width as usize * height as usize;
+        Framebuffer {
+            width,
+            height,
+            sums: vec![Color::default(); size],
+            counts: vec![0; size],
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height, "pixel ({}, {}) out of range", x, y);
+        y as usize * self.width as usize + x as usize
+    }
+
+    pub fn add_sample(&mut self, x: u32, y: u32, color: Color) {
+        self.add_samples(x, y, color, 1);
+    }
+
+    /// Adds `count` samples whose colors sum up to `sum`.
+    pub fn add_samples(&mut self, x: u32, y: u32, sum: Color, count: u32) {
+        let idx = self.index(x, y);
+        self.sums[idx] = self.sums[idx] + sum;
+        self.counts[idx] += count;
+    }
+
+    /// Adds every sample of `other`, which must have the same size.
+    pub fn merge(&mut self, other: &Framebuffer) {
+        assert_eq!((self.width, self.height), (other.width, other.height));
+        for (sum, other_sum) in self.sums.iter_mut().zip(other.sums.iter()) {
+            *sum = *sum + *other_sum;
+        }
+        for (count, other_count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += *other_count;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.sums.fill(Color::default());
+        self.counts.fill(0);
+    }
+
+    pub fn sum(&self, x: u32, y: u32) -> Color {
+        self.sums[self.index(x, y)]
+    }
+
+    pub fn count(&self, x: u32, y: u32) -> u32 {
+        self.counts[self.index(x, y)]
+    }
+
+    /// Mean of the samples at a pixel, black if it has none yet.
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        let idx = self.index(x, y);
+        mean(self.sums[idx], self.counts[idx])
+    }
+
+    /// Mean colors of all pixels, row by row from the top-left corner.
+    pub fn colors(&self) -> impl Iterator<Item = Color> + '_ {
+        self.sums.iter().zip(self.counts.iter()).map(|(&sum, &count)| mean(sum, count))
+    }
+
+    /// Converts to an 8-bit image. Pixels without any samples come out
+    /// fully transparent.
+    pub fn resolve(&self, settings: &ResolveSettings) -> Image {
+        let mut image = Image::new(self.width, self.height);
+        for ((pixel, color), &count) in image.data.iter_mut().zip(self.colors()).zip(self.counts.iter()) {
+            *pixel = Pixel {
+                r: quantize(settings.apply(color.x)),
+                g: quantize(settings.apply(color.y)),
+                b: quantize(settings.apply(color.z)),
+                a: if count > 0 { 0xff } else { 0x00 },
+            };
+        }
+        image
+    }
+
+    /// Mean colors without any tone mapping, e.g. for saving as `.hdr`.
+    pub fn to_hdr(&self) -> HdrImage {
+        HdrImage { width: self.width, height: self.height, data: self.colors().collect() }
+    }
+
+    /// Encodes the framebuffer as a 16-bit RGBA PNG, keeping more of the
+    /// float precision than going through `resolve`.
+    pub fn write_png<W: Write>(&self, w: W, settings: &ResolveSettings) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(self.sums.len() * 8);
+        for (color, &count) in self.colors().zip(self.counts.iter()) {
+            let alpha = if count > 0 { u16::MAX } else { 0 };
+            for c in [settings.apply(color.x), settings.apply(color.y), settings.apply(color.z)] {
+                bytes.extend_from_slice(&((c * 65535.0 + 0.5) as u16).to_be_bytes());
+            }
+            bytes.extend_from_slice(&alpha.to_be_bytes());
+        }
+        write_png_rgba(w, self.width, self.height, png::BitDepth::Sixteen, &bytes)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P, settings: &ResolveSettings) -> io::Result<()> {
+        self.write_png(BufWriter::new(File::create(path)?), settings)
+    }
+}
+
+fn mean(sum: Color, count: u32) -> Color {
+    if count == 0 {
+        Color::default()
+    } else {
+        sum / count as f32
+    }
+}
+
+fn quantize(c: f32) -> u8 {
+    (clamp(c, 0.0, 0.999) * 256.0) as u8
+}
@@ -0,0 +1,273 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::math::Color;
+
+/// Linear floating-point image, as stored in Radiance `.hdr` files.
+#[derive(Debug, Default, Clone)]
+pub struct HdrImage {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major, starting at the top-left corner.
+    pub data: Vec<Color>,
+}
+
+#[derive(Debug)]
+pub enum HdrError {
+    Io(io::Error),
+    /// The file does not start with `#?`.
+    BadSignature,
+    /// A `FORMAT=` other than `32-bit_rle_rgbe`.
+    UnsupportedFormat(String),
+    /// A resolution line this reader does not understand, or an empty image.
+    BadResolution(String),
+    UnexpectedEof,
+    /// Run-length encoded data that does not add up to the scanline width.
+    BadScanline(u32),
+}
+
+impl fmt::Display for HdrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HdrError::Io(e) => write!(f, "I/O error: {}", e),
+            HdrError::BadSignature => write!(f, "not a Radiance HDR file"),
+            HdrError::UnsupportedFormat(format) => write!(f, "unsupported pixel format {:?}", format),
+            HdrError::BadResolution(line) => write!(f, "unsupported resolution line {:?}", line),
+            HdrError::UnexpectedEof => write!(f, "unexpected end of file"),
+            HdrError::BadScanline(y) => write!(f, "corrupt run-length data in scanline {}", y),
+        }
+    }
+}
+
+impl Error for HdrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HdrError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HdrError {
+    fn from(e: io::Error) -> Self {
+        HdrError::Io(e)
+    }
+}
+
+impl HdrImage {
+    pub fn new(width: u32, height: u32) -> Self {
+        let data = vec![Color::default(); width as usize * height as usize];
+        HdrImage { width, height, data }
+    }
+
+    pub fn from_path<P: AsRef<Path>>(filepath: P) -> Result<Self, HdrError> {
+        read(BufReader::new(File::open(filepath)?))
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> Color {
+        self.data[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Writes uncompressed RGBE scanlines.
+    pub fn write_hdr<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write!(w, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", self.height, self.width)?;
+        let bytes: Vec<u8> = self.data.iter().flat_map(|&c| to_rgbe(c)).collect();
+        w.write_all(&bytes)?;
+        w.flush()
+    }
+
+    pub fn save_hdr<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_hdr(BufWriter::new(File::create(path)?))
+    }
+}
+
+fn from_rgbe(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::default();
+    }
+    let f = 2.0f32.powi(rgbe[3] as i32 - (128 + 8));
+    Color::new(rgbe[0] as f32 + 0.5, rgbe[1] as f32 + 0.5, rgbe[2] as f32 + 0.5) * f
+}
+
+fn to_rgbe(c: Color) -> [u8; 4] {
+    let v = c.x.max(c.y).max(c.z);
+    if v.is_nan() || v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+    // Infinite channels, say from a firefly, saturate at the largest value.
+    let v = v.min(f32::MAX);
+    // v = m * 2^e with m in [0.5, 1)
+    let e = v.log2().floor() as i32 + 1;
+    let e = e.clamp(-128, 127);
+    let scale = 256.0 / 2.0f32.powi(e);
+    let channel = |x: f32| (x.max(0.0) * scale).min(255.0) as u8;
+    [channel(c.x), channel(c.y), channel(c.z), (e + 128) as u8]
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn line(&mut self) -> Result<&'a str, HdrError> {
+        if self.pos >= self.data.len() {
+            return Err(HdrError::UnexpectedEof);
+        }
+        let rest = &self.data[self.pos..];
+        let len = rest.iter().position(|&c| c == b'\n').unwrap_or(rest.len());
+        self.pos += (len + 1).min(rest.len());
+        Ok(std::str::from_utf8(&rest[..len]).unwrap_or("").trim_end_matches('\r'))
+    }
+
+    fn byte(&mut self) -> Result<u8, HdrError> {
+        let b = *self.data.get(self.pos).ok_or(HdrError::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn rgbe(&mut self) -> Result<[u8; 4], HdrError> {
+        Ok([self.byte()?, self.byte()?, self.byte()?, self.byte()?])
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+}
+
+pub fn read<R: Read>(mut reader: R) -> Result<HdrImage, HdrError> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    decode(&data)
+}
+
+/// Decodes a Radiance RGBE file, flat or run-length encoded (old and new style).
+pub fn decode(data: &[u8]) -> Result<HdrImage, HdrError> {
+    if !data.starts_with(b"#?") {
+        return Err(HdrError::BadSignature);
+    }
+    let mut reader = Reader { data, pos: 0 };
+    reader.line()?;
+
+    loop {
+        let line = reader.line()?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(HdrError::UnsupportedFormat(format.to_string()));
+            }
+        }
+    }
+
+    let resolution = reader.line()?;
+    let fields: Vec<&str> = resolution.split_whitespace().collect();
+    let bad_resolution = || HdrError::BadResolution(resolution.to_string());
+    let (flip_y, height, width) = match fields.as_slice() {
+        ["-Y", h, "+X", w] => (false, h, w),
+        ["+Y", h, "+X", w] => (true, h, w),
+        _ => return Err(bad_resolution()),
+    };
+    let height: u32 = height.parse().map_err(|_| bad_resolution())?;
+    let width: u32 = width.parse().map_err(|_| bad_resolution())?;
+    if width == 0 || height == 0 {
+        return Err(bad_resolution());
+    }
+
+    let pixels = (width as usize).checked_mul(height as usize).ok_or_else(bad_resolution)?;
+    // Every scanline takes at least one RGBE value, so a header promising
+    // more rows than the file holds fails here instead of allocating them.
+    if reader.remaining() / 4 < height as usize {
+        return Err(HdrError::UnexpectedEof);
+    }
+
+    // Rows are appended as they decode, so memory follows the data actually
+    // present rather than the size the header claims.
+    let mut data = Vec::with_capacity(pixels.min(reader.remaining()));
+    let mut scanline = vec![];
+    for y in 0..height {
+        read_scanline(&mut reader, &mut scanline, width as usize, y)?;
+        data.extend(scanline.iter().map(|&rgbe| from_rgbe(rgbe)));
+    }
+    if flip_y {
+        data = data.chunks(width as usize).rev().flatten().copied().collect();
+    }
+
+    Ok(HdrImage { width, height, data })
+}
+
+fn read_scanline(reader: &mut Reader, scanline: &mut Vec<[u8; 4]>, width: usize, y: u32) -> Result<(), HdrError> {
+    scanline.clear();
+    let first = reader.rgbe()?;
+
+    let is_new_rle = (8..0x8000).contains(&width) && first[0] == 2 && first[1] == 2 && first[2] & 0x80 == 0;
+    if !is_new_rle {
+        return read_flat_scanline(reader, scanline, width, first, y);
+    }
+    if ((first[2] as usize) << 8 | first[3] as usize) != width {
+        return Err(HdrError::BadScanline(y));
+    }
+    scanline.resize(width, [0; 4]);
+
+    // Each channel is stored separately as runs and literal dumps.
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = reader.byte()? as usize;
+            if count > 128 {
+                let count = count - 128;
+                let value = reader.byte()?;
+                if x + count > width {
+                    return Err(HdrError::BadScanline(y));
+                }
+                for px in &mut scanline[x..x + count] {
+                    px[channel] = value;
+                }
+                x += count;
+            } else {
+                if count == 0 || x + count > width {
+                    return Err(HdrError::BadScanline(y));
+                }
+                for px in &mut scanline[x..x + count] {
+                    px[channel] = reader.byte()?;
+                }
+                x += count;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_flat_scanline(reader: &mut Reader, scanline: &mut Vec<[u8; 4]>, width: usize, first: [u8; 4], y: u32) -> Result<(), HdrError> {
+    let mut shift = 0;
+    let mut next = Some(first);
+    while scanline.len() < width {
+        let rgbe = match next.take() {
+            Some(rgbe) => rgbe,
+            None => reader.rgbe()?,
+        };
+        if rgbe[0] == 1 && rgbe[1] == 1 && rgbe[2] == 1 {
+            // Old-style run: repeat the previous pixel.
+            let Some(&previous) = scanline.last() else {
+                return Err(HdrError::BadScanline(y));
+            };
+            if shift > 24 {
+                return Err(HdrError::BadScanline(y));
+            }
+            let count = (rgbe[3] as usize) << shift;
+            if scanline.len() + count > width {
+                return Err(HdrError::BadScanline(y));
+            }
+            scanline.resize(scanline.len() + count, previous);
+            shift += 8;
+        } else {
+            scanline.push(rgbe);
+            shift = 0;
+        }
+    }
+    Ok(())
+}
@@ -1,16 +1,37 @@
-use crate::math::{Ray, Vec3, Point3};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::math::{Ray, Vec3, Point3, Color, Transform, AnimatedTransform};
 use crate::material::Material;
+use crate::aabb::Aabb;
 
-pub struct HitRecord {
+/// What a ray hit. The material is borrowed from the object, so finding a
+/// hit never allocates.
+pub struct HitRecord<'a> {
     pub p: Point3,
     pub normal: Vec3,
     pub t: f32,
+    /// Surface coordinates of `p`, for texture lookups.
+    pub u: f32,
+    pub v: f32,
+    /// Per-vertex color interpolated at `p`, for meshes that carry one.
+    pub color: Option<Color>,
     pub front_face: bool,
-    pub material: Box<dyn Material>,
+    pub material: &'a dyn Material,
 }
 
-pub trait Hittable {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+pub trait Hittable: Send + Sync {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>>;
+
+    /// Box enclosing the whole object, or `None` if it is unbounded.
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// Fraction of the light travelling along `ray` between `t_min` and
+    /// `t_max` that gets through. Surfaces block it completely; media
+    /// override this to let part of it pass.
+    fn transmittance(&self, ray: &Ray, t_min: f32, t_max: f32) -> f32 {
+        if self.hit(ray, t_min, t_max).is_some() { 0.0 } else { 1.0 }
+    }
 }
 
 pub struct Sphere {
@@ -19,37 +40,645 @@ pub struct Sphere {
     pub material: Box<dyn Material>,
 }
 
+impl Sphere {
+    /// Surface coordinates of a point on the unit sphere: `u` runs around the
+    /// y axis starting at -x, `v` from the bottom (0) to the top (1).
+    pub fn uv(p: Point3) -> (f32, f32) {
+        let theta = (-p.y).clamp(-1.0, 1.0).acos();
+        let phi = (-p.z).atan2(p.x) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+}
+
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        let oc: Vec3 = ray.origin - self.center;
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        hit_sphere(self.center, self.radius, &*self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        Some(Aabb::from_points(self.center - r, self.center + r))
+    }
+}
+
+/// Nearest intersection within `t_min..t_max` with a sphere; a negative
+/// `radius` turns its normals inward.
+fn hit_sphere<'a>(
+    center: Point3,
+    radius: f32,
+    material: &'a dyn Material,
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+) -> Option<HitRecord<'a>> {
+    let oc: Vec3 = ray.origin - center;
 
-        let a = Vec3::dot(ray.direction, ray.direction);
-        let b = Vec3::dot(oc, ray.direction) * 2.0;
-        let c = Vec3::dot(oc, oc) - self.radius * self.radius;
-        let disc = b * b - 4.0 * a * c;
+    let a = Vec3::dot(ray.direction, ray.direction);
+    let half_b = Vec3::dot(oc, ray.direction);
+    let c = Vec3::dot(oc, oc) - radius * radius;
+    let disc = half_b * half_b - a * c;
+    if disc < 0.0 {
+        return None;
+    }
 
-        if disc < 0.0 {
+    let sqrt_disc = disc.sqrt();
+    let mut root = (-half_b - sqrt_disc) / a;
+    if root < t_min || t_max < root {
+        root = (-half_b + sqrt_disc) / a;
+        if root < t_min || t_max < root {
             return None;
         }
+    }
 
-        let mut root = (-b - disc.sqrt()) / (2.0 * a);
-        if root < t_min || t_max < root {
-            root = (-b + disc.sqrt()) / (2.0 * a);
-            if root < t_min || t_max < root {
-                return None;
-            }
+    let t = root;
+    let p = ray.at(t);
+    let outward_normal = (p - center) / radius;
+    let front_face = Vec3::dot(ray.direction, outward_normal) < 0.0;
+    let normal = if front_face { outward_normal } else { -outward_normal };
+    let (u, v) = Sphere::uv(outward_normal);
+
+    Some(HitRecord {
+        t, p, normal, u, v, front_face,
+        color: None,
+        material,
+    })
+}
+
+/// Sphere moving in a straight line from `center0` at `time0` to `center1`
+/// at `time1`, and resting at either end outside that interval.
+pub struct MovingSphere {
+    pub center0: Point3,
+    pub center1: Point3,
+    pub time0: f32,
+    pub time1: f32,
+    pub radius: f32,
+    pub material: Box<dyn Material>,
+}
+
+impl MovingSphere {
+    pub fn center(&self, time: f32) -> Point3 {
+        if self.time1 <= self.time0 {
+            return self.center0;
+        }
+        let t = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        self.center0 + (self.center1 - self.center0) * t
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        hit_sphere(self.center(ray.time), self.radius, &*self.material, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        let start = Aabb::from_points(self.center0 - r, self.center0 + r);
+        let end = Aabb::from_points(self.center1 - r, self.center1 + r);
+        Some(Aabb::surrounding(start, end))
+    }
+}
+
+/// Flat or smooth-shaded triangle. Vertices wound counter-clockwise, seen
+/// from the side the geometric normal points to, make up the front face.
+pub struct Triangle {
+    pub vertices: [Point3; 3],
+    /// Per-vertex shading normals; the geometric normal is used if `None`.
+    pub normals: Option<[Vec3; 3]>,
+    /// Per-vertex surface coordinates; the barycentric weights are used if `None`.
+    pub uvs: Option<[(f32, f32); 3]>,
+    pub material: Box<dyn Material>,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, material: Box<dyn Material>) -> Self {
+        Triangle { vertices: [a, b, c], normals: None, uvs: None, material }
+    }
+
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f32, f32); 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+/// Möller–Trumbore ray/triangle test. Returns `t` and the barycentric
+/// weights of the second and third vertex; both faces are hit.
+pub fn intersect_triangle(ray: &Ray, vertices: &[Point3; 3], t_min: f32, t_max: f32) -> Option<(f32, f32, f32)> {
+    let [v0, v1, v2] = *vertices;
+    let e1 = v1 - v0;
+    let e2 = v2 - v0;
+    let pvec = Vec3::cross(ray.direction, e2);
+    let det = Vec3::dot(e1, pvec);
+    // Parallel ray or degenerate triangle.
+    if det == 0.0 || det.is_nan() {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let tvec = ray.origin - v0;
+    let b1 = Vec3::dot(tvec, pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = Vec3::cross(tvec, e1);
+    let b2 = Vec3::dot(ray.direction, qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = Vec3::dot(e2, qvec) * inv_det;
+    if t < t_min || t_max < t {
+        return None;
+    }
+    Some((t, b1, b2))
+}
+
+/// Fills a hit record from barycentric weights, interpolating normals,
+/// surface coordinates and colors when they are given.
+pub(crate) fn triangle_record<'a>(
+    ray: &Ray,
+    (t, b1, b2): (f32, f32, f32),
+    vertices: &[Point3; 3],
+    normals: Option<&[Vec3; 3]>,
+    uvs: Option<&[(f32, f32); 3]>,
+    colors: Option<&[Color; 3]>,
+    material: &'a dyn Material,
+) -> HitRecord<'a> {
+    let b0 = 1.0 - b1 - b2;
+    let geometric = Vec3::cross(vertices[1] - vertices[0], vertices[2] - vertices[0]).normalized();
+    let (geometric, outward_normal) = match normals {
+        Some(n) => {
+            let shading = (n[0] * b0 + n[1] * b1 + n[2] * b2).normalized();
+            // Vertex normals say which side is outside, whatever the winding;
+            // turn the geometric normal to match before deciding the face.
+            let geometric = if Vec3::dot(geometric, shading) < 0.0 { -geometric } else { geometric };
+            (geometric, shading)
+        },
+        None => (geometric, geometric),
+    };
+    let front_face = Vec3::dot(ray.direction, geometric) < 0.0;
+    let normal = if front_face { outward_normal } else { -outward_normal };
+
+    let (u, v) = match uvs {
+        Some(uv) => (
+            uv[0].0 * b0 + uv[1].0 * b1 + uv[2].0 * b2,
+            uv[0].1 * b0 + uv[1].1 * b1 + uv[2].1 * b2,
+        ),
+        None => (b1, b2),
+    };
+
+    let color = colors.map(|c| c[0] * b0 + c[1] * b1 + c[2] * b2);
+
+    HitRecord {
+        t, p: ray.at(t), normal, u, v, color, front_face,
+        material,
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let hit = intersect_triangle(ray, &self.vertices, t_min, t_max)?;
+        Some(triangle_record(ray, hit, &self.vertices, self.normals.as_ref(), self.uvs.as_ref(), None, &*self.material))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [a, b, c] = self.vertices;
+        Some(Aabb::from_points(a, b).grow(c))
+    }
+}
+
+/// Infinite plane through `point`, facing along `normal`. Its surface
+/// coordinates are distances from `point` along two fixed directions in
+/// the plane, so they grow without bound; textures should repeat.
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    material: Box<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, material: Box<dyn Material>) -> Self {
+        let normal = normal.normalized();
+        let (tangent, bitangent) = Vec3::orthonormal_basis(normal);
+        Plane { point, normal, tangent, bitangent, material }
+    }
+
+    pub fn point(&self) -> Point3 {
+        self.point
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let denom = Vec3::dot(self.normal, ray.direction);
+        if denom == 0.0 {
+            return None;
+        }
+        let t = Vec3::dot(self.point - ray.origin, self.normal) / denom;
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let front_face = denom < 0.0;
+        let offset = p - self.point;
+        Some(HitRecord {
+            t, p, front_face,
+            normal: if front_face { self.normal } else { -self.normal },
+            u: Vec3::dot(offset, self.tangent),
+            v: Vec3::dot(offset, self.bitangent),
+            color: None,
+            material: &*self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+// Thickness given to the bounding boxes of flat shapes, so that the slab
+// test never sees a box of zero width.
+const FLAT_PADDING: f32 = 1e-4;
+
+/// Hit on the rectangle spanning `a0..a1` along axis `a` and `b0..b1` along
+/// axis `b`, at `k` on the remaining axis. `normal` points along that axis.
+#[allow(clippy::too_many_arguments)]
+fn axis_rect_hit<'a>(
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+    (a, b, c): (usize, usize, usize),
+    [a0, a1, b0, b1, k]: [f32; 5],
+    normal: Vec3,
+    material: &'a dyn Material,
+) -> Option<HitRecord<'a>> {
+    let t = (k - ray.origin[c]) / ray.direction[c];
+    if !(t_min..=t_max).contains(&t) {
+        return None;
+    }
+    let p = ray.at(t);
+    if !(a0..=a1).contains(&p[a]) || !(b0..=b1).contains(&p[b]) {
+        return None;
+    }
+
+    let front_face = Vec3::dot(ray.direction, normal) < 0.0;
+    Some(HitRecord {
+        t, p, front_face,
+        normal: if front_face { normal } else { -normal },
+        u: (p[a] - a0) / (a1 - a0),
+        v: (p[b] - b0) / (b1 - b0),
+        color: None,
+        material,
+    })
+}
+
+/// Orders `a0, a1` and `b0, b1`, panicking unless both spans have a length.
+fn rect_bounds(shape: &str, [a0, a1, b0, b1]: [f32; 4]) -> [f32; 4] {
+    assert!((a1 - a0).abs() > 0.0 && (b1 - b0).abs() > 0.0, "{} has no area", shape);
+    [a0.min(a1), a0.max(a1), b0.min(b1), b0.max(b1)]
+}
+
+/// Rectangle in the plane `z = k`, facing +z, with `u` along x and `v`
+/// along y.
+pub struct XyRect {
+    x0: f32,
+    x1: f32,
+    y0: f32,
+    y1: f32,
+    k: f32,
+    material: Box<dyn Material>,
+}
+
+impl XyRect {
+    /// The bounds may be given in either order, but must not coincide.
+    pub fn new(x0: f32, x1: f32, y0: f32, y1: f32, k: f32, material: Box<dyn Material>) -> Self {
+        let [x0, x1, y0, y1] = rect_bounds("XyRect", [x0, x1, y0, y1]);
+        XyRect { x0, x1, y0, y1, k, material }
+    }
+}
+
+impl Hittable for XyRect {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let bounds = [self.x0, self.x1, self.y0, self.y1, self.k];
+        axis_rect_hit(ray, t_min, t_max, (0, 1, 2), bounds, Vec3::new(0.0, 0.0, 1.0), &*self.material)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let min = Point3::new(self.x0, self.y0, self.k - FLAT_PADDING);
+        let max = Point3::new(self.x1, self.y1, self.k + FLAT_PADDING);
+        Some(Aabb::from_points(min, max))
+    }
+}
+
+/// Rectangle in the plane `y = k`, facing +y, with `u` along x and `v`
+/// along z.
+pub struct XzRect {
+    x0: f32,
+    x1: f32,
+    z0: f32,
+    z1: f32,
+    k: f32,
+    material: Box<dyn Material>,
+}
+
+impl XzRect {
+    /// See `XyRect::new`.
+    pub fn new(x0: f32, x1: f32, z0: f32, z1: f32, k: f32, material: Box<dyn Material>) -> Self {
+        let [x0, x1, z0, z1] = rect_bounds("XzRect", [x0, x1, z0, z1]);
+        XzRect { x0, x1, z0, z1, k, material }
+    }
+}
+
+impl Hittable for XzRect {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let bounds = [self.x0, self.x1, self.z0, self.z1, self.k];
+        axis_rect_hit(ray, t_min, t_max, (0, 2, 1), bounds, Vec3::new(0.0, 1.0, 0.0), &*self.material)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let min = Point3::new(self.x0, self.k - FLAT_PADDING, self.z0);
+        let max = Point3::new(self.x1, self.k + FLAT_PADDING, self.z1);
+        Some(Aabb::from_points(min, max))
+    }
+}
+
+/// Rectangle in the plane `x = k`, facing +x, with `u` along y and `v`
+/// along z.
+pub struct YzRect {
+    y0: f32,
+    y1: f32,
+    z0: f32,
+    z1: f32,
+    k: f32,
+    material: Box<dyn Material>,
+}
+
+impl YzRect {
+    /// See `XyRect::new`.
+    pub fn new(y0: f32, y1: f32, z0: f32, z1: f32, k: f32, material: Box<dyn Material>) -> Self {
+        let [y0, y1, z0, z1] = rect_bounds("YzRect", [y0, y1, z0, z1]);
+        YzRect { y0, y1, z0, z1, k, material }
+    }
+}
+
+impl Hittable for YzRect {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let bounds = [self.y0, self.y1, self.z0, self.z1, self.k];
+        axis_rect_hit(ray, t_min, t_max, (1, 2, 0), bounds, Vec3::new(1.0, 0.0, 0.0), &*self.material)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let min = Point3::new(self.k - FLAT_PADDING, self.y0, self.z0);
+        let max = Point3::new(self.k + FLAT_PADDING, self.y1, self.z1);
+        Some(Aabb::from_points(min, max))
+    }
+}
+
+/// Parallelogram with corners `q`, `q + u`, `q + u + v` and `q + v`, facing
+/// along `u x v`. The surface coordinates run from 0 to 1 along `u` and `v`.
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    // `n / |n|^2` for the unnormalized normal `n`, which turns cross products
+    // with `u` and `v` into coordinates along them.
+    w: Vec3,
+    material: Box<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: Box<dyn Material>) -> Self {
+        let n = Vec3::cross(u, v);
+        let w = n / Vec3::dot(n, n);
+        Quad { q, u, v, normal: n.normalized(), w, material }
+    }
+
+    pub fn corner(&self) -> Point3 {
+        self.q
+    }
+
+    pub fn edges(&self) -> (Vec3, Vec3) {
+        (self.u, self.v)
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let denom = Vec3::dot(self.normal, ray.direction);
+        // Parallel rays, and degenerate quads whose normal is NaN.
+        if denom == 0.0 || denom.is_nan() {
+            return None;
+        }
+        let t = Vec3::dot(self.q - ray.origin, self.normal) / denom;
+        if !(t_min..=t_max).contains(&t) {
+            return None;
         }
 
-        let t = root;
         let p = ray.at(t);
-        let outward_normal = (p - self.center) / self.radius;
-        let front_face = Vec3::dot(ray.direction, outward_normal) < 0.0;
-        let normal = if front_face { outward_normal } else { -outward_normal };
+        let offset = p - self.q;
+        let u = Vec3::dot(self.w, Vec3::cross(offset, self.v));
+        let v = Vec3::dot(self.w, Vec3::cross(self.u, offset));
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return None;
+        }
+
+        let front_face = denom < 0.0;
+        Some(HitRecord {
+            t, p, u, v, front_face,
+            normal: if front_face { self.normal } else { -self.normal },
+            color: None,
+            material: &*self.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bbox = Aabb::from_points(self.q, self.q + self.u + self.v)
+            .grow(self.q + self.u)
+            .grow(self.q + self.v);
+        Some(bbox.padded(FLAT_PADDING))
+    }
+}
+
+/// Axis-aligned box between two opposite corners, made of six outward
+/// facing quads that each carry their own surface coordinates. Turn it with
+/// an `Instance`.
+pub struct Cuboid {
+    sides: [Quad; 6],
+    bbox: Aabb,
+}
+
+impl Cuboid {
+    pub fn new(a: Point3, b: Point3, material: Box<dyn Material>) -> Self {
+        let bbox = Aabb::from_points(a, b);
+        let (min, max) = (bbox.min, bbox.max);
+        let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+
+        let side = |q: Point3, u: Vec3, v: Vec3| Quad::new(q, u, v, dyn_clone::clone_box(&*material));
+        let sides = [
+            side(Point3::new(min.x, min.y, max.z), dx, dy),
+            side(Point3::new(max.x, min.y, max.z), -dz, dy),
+            side(Point3::new(max.x, min.y, min.z), -dx, dy),
+            side(min, dz, dy),
+            side(Point3::new(min.x, max.y, max.z), dx, -dz),
+            side(min, dx, dz),
+        ];
+        Cuboid { sides, bbox }
+    }
+
+    pub fn sides(&self) -> &[Quad; 6] {
+        &self.sides
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        if !self.bbox.padded(FLAT_PADDING).hit(ray, t_min, t_max) {
+            return None;
+        }
+        let mut closest_so_far = t_max;
+        let mut closest_rec = None;
+        for side in &self.sides {
+            if let Some(rec) = side.hit(ray, t_min, closest_so_far) {
+                closest_so_far = rec.t;
+                closest_rec = Some(rec);
+            }
+        }
+        closest_rec
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox.padded(FLAT_PADDING))
+    }
+}
+
+/// Shared object placed in the world by a transform. Rays are moved into
+/// the object's space and hits back out, so one mesh can be placed many
+/// times without copying it.
+pub struct Instance {
+    object: Arc<dyn Hittable>,
+    transform: Transform,
+    bbox: Option<Aabb>,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Self {
+        let bbox = object.bounding_box().map(|b| b.transformed(&transform));
+        Instance { object, transform, bbox }
+    }
+
+    pub fn object(&self) -> &Arc<dyn Hittable> {
+        &self.object
+    }
 
-        return Some(HitRecord {
-            t, p, normal, front_face,
-            material: dyn_clone::clone_box(&*self.material),
-        });
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let local = self.transform.inverse().transform_ray(ray);
+        let mut rec = self.object.hit(&local, t_min, t_max)?;
+        // `t` means the same in both spaces. The inverse transpose keeps the
+        // normal facing the ray, so `front_face` carries over as well.
+        rec.p = ray.at(rec.t);
+        rec.normal = self.transform.transform_normal(rec.normal).normalized();
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bbox
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f32, t_max: f32) -> f32 {
+        self.object.transmittance(&self.transform.inverse().transform_ray(ray), t_min, t_max)
+    }
+}
+
+/// Like `Instance`, with the transform following `motion` over time.
+pub struct MovingInstance {
+    object: Arc<dyn Hittable>,
+    motion: AnimatedTransform,
+    bbox: Option<Aabb>,
+}
+
+// Times at which the motion is sampled to bound it.
+const MOTION_BOUND_STEPS: usize = 16;
+
+impl MovingInstance {
+    pub fn new(object: Arc<dyn Hittable>, motion: AnimatedTransform) -> Self {
+        let bbox = object.bounding_box().map(|b| MovingInstance::motion_bounds(&b, &motion));
+        MovingInstance { object, motion, bbox }
+    }
+
+    pub fn object(&self) -> &Arc<dyn Hittable> {
+        &self.object
+    }
+
+    pub fn motion(&self) -> &AnimatedTransform {
+        &self.motion
+    }
+
+    /// Box around `b` over the whole motion. Scale and translation move each
+    /// corner in a straight line, so boxes at a few times cover them; the
+    /// arc a rotating corner bulges out of its chords by is padded on.
+    fn motion_bounds(b: &Aabb, motion: &AnimatedTransform) -> Aabb {
+        let (time0, time1) = motion.times();
+        if !motion.is_animated() {
+            return b.transformed(&motion.at(time0));
+        }
+
+        let step_angle = motion.rotation_angle() / MOTION_BOUND_STEPS as f32;
+        let mut bounds = Aabb::empty();
+        let mut reach: f32 = 0.0;
+        for i in 0..=MOTION_BOUND_STEPS {
+            let time = time0 + (time1 - time0) * i as f32 / MOTION_BOUND_STEPS as f32;
+            let transform = motion.at(time);
+            let origin = transform.transform_point(Point3::default());
+            for corner in b.corners() {
+                let p = transform.transform_point(corner);
+                reach = reach.max((p - origin).length());
+                bounds = bounds.grow(p);
+            }
+        }
+        bounds.padded(reach * (1.0 - (step_angle / 2.0).cos()))
+    }
+}
+
+impl Hittable for MovingInstance {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let transform = self.motion.at(ray.time);
+        let local = transform.inverse().transform_ray(ray);
+        let mut rec = self.object.hit(&local, t_min, t_max)?;
+        rec.p = ray.at(rec.t);
+        rec.normal = transform.transform_normal(rec.normal).normalized();
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bbox
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f32, t_max: f32) -> f32 {
+        let local = self.motion.at(ray.time).inverse().transform_ray(ray);
+        self.object.transmittance(&local, t_min, t_max)
     }
 }
 
@@ -59,17 +688,34 @@ pub struct HittableList {
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
         let mut closest_so_far = t_max;
-        let mut closest_rec: Option<HitRecord> = None;
+        let mut closest_rec: Option<HitRecord<'_>> = None;
 
         for hittable in self.hittables.iter() {
-            if let Some(rec) = hittable.hit(&ray, t_min, closest_so_far) {
+            if let Some(rec) = hittable.hit(ray, t_min, closest_so_far) {
                 closest_so_far = rec.t;
                 closest_rec = Some(rec);
             }
         }
 
-        return closest_rec;
+        closest_rec
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut boxes = self.hittables.iter().map(|h| h.bounding_box());
+        let first = boxes.next()??;
+        boxes.try_fold(first, |acc, b| Some(Aabb::surrounding(acc, b?)))
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f32, t_max: f32) -> f32 {
+        let mut transmittance = 1.0;
+        for hittable in self.hittables.iter() {
+            transmittance *= hittable.transmittance(ray, t_min, t_max);
+            if transmittance == 0.0 {
+                break;
+            }
+        }
+        transmittance
     }
 }
@@ -1,5 +1,10 @@
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::color::decode_gamma;
+use crate::math::Color;
+use crate::pnm::{self, PnmError, PpmEncoding};
 
 #[derive(Clone, Copy)]
 pub struct Pixel {
@@ -15,6 +20,13 @@ impl Default for Pixel {
     }
 }
 
+impl Pixel {
+    /// Linear color, undoing the gamma 2 encoding the renderer writes.
+    pub(crate) fn to_linear(self) -> Color {
+        decode_gamma(Color::new(self.r as f32, self.g as f32, self.b as f32) / 255.0)
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct Image {
     pub width: u32,
@@ -23,41 +35,78 @@ pub struct Image {
 }
 
 impl Image {
+    /// Every pixel as a linear color; see `Pixel::to_linear`.
+    pub(crate) fn to_linear(&self) -> Vec<Color> {
+        self.data.iter().map(|px| px.to_linear()).collect()
+    }
+
     pub fn new(width: u32, height: u32) -> Self {
         let size = width as usize * height as usize;
         let data = vec![Pixel::default(); size];
         Image { width, height, data }
     }
 
-    pub fn from_path(filepath: &str) -> Self {
-        let f = File::open(filepath)
-            .unwrap_or_else(|_| panic!("Couldn't open the file: {}", filepath));
-        let mut data: Vec<Pixel> = Vec::new();
-
-        let mut width: u32 = 0;
-        let mut height: u32 = 0;
-        for (k, result) in BufReader::new(f).lines().enumerate() {
-            let l = result.unwrap_or_else(|e| panic!("something went wrong reading the file: {}", e));
-            if l == "P3" {
-                println!("P3 is detected");
-            } else if k == 1 {
-                let d = l.split(" ").collect::<Vec<&str>>();
-                width = d[0].parse::<u32>().unwrap();
-                height = d[1].parse::<u32>().unwrap();
-            } else if k >= 3 {
-                let d = l.split(" ").collect::<Vec<&str>>();
-                let r = d[0].parse::<u8>().unwrap();
-                let g = d[1].parse::<u8>().unwrap();
-                let b = d[2].parse::<u8>().unwrap();
-                let a = 0xff;
-                data.push(Pixel { r, g, b, a });
-            }
-        }
+    /// Reads a PNM (P1 .. P6) file.
+    pub fn from_path<P: AsRef<Path>>(filepath: P) -> Result<Self, PnmError> {
+        pnm::read(BufReader::new(File::open(filepath)?))
+    }
 
-        Image { width, height, data }
+    /// Decodes a PNG of any color type. Palettes are expanded and 16-bit
+    /// samples cut to 8 bits; gray images are spread over r, g and b.
+    pub fn read_png<R: Read>(reader: R) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let channels = info.color_type.samples();
+        let data = buf[..info.buffer_size()].chunks_exact(channels).map(|px| match *px {
+            [v] => Pixel { r: v, g: v, b: v, a: 255 },
+            [v, a] => Pixel { r: v, g: v, b: v, a },
+            [r, g, b] => Pixel { r, g, b, a: 255 },
+            [r, g, b, a, ..] => Pixel { r, g, b, a },
+            _ => unreachable!(),
+        }).collect();
+        Ok(Image { width: info.width, height: info.height, data })
+    }
+
+    pub fn from_png_path<P: AsRef<Path>>(filepath: P) -> Result<Self, png::DecodingError> {
+        Image::read_png(BufReader::new(File::open(filepath)?))
     }
 
     pub fn get_pixel(&self, idx: usize) -> Pixel {
-        return self.data[idx];
+        self.data[idx]
     }
+
+    pub fn write_ppm<W: Write>(&self, w: W, encoding: PpmEncoding) -> io::Result<()> {
+        pnm::write_ppm(self, w, encoding)
+    }
+
+    pub fn save_ppm<P: AsRef<Path>>(&self, path: P, encoding: PpmEncoding) -> io::Result<()> {
+        self.write_ppm(BufWriter::new(File::create(path)?), encoding)
+    }
+
+    /// Encodes the image as an 8-bit RGBA PNG.
+    pub fn write_png<W: Write>(&self, w: W) -> io::Result<()> {
+        let bytes: Vec<u8> = self.data.iter()
+            .flat_map(|px| [px.r, px.g, px.b, px.a])
+            .collect();
+        write_png_rgba(w, self.width, self.height, png::BitDepth::Eight, &bytes)
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_png(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Writes RGBA samples (big-endian for 16-bit depth) as a PNG stream.
+pub(crate) fn write_png_rgba<W: Write>(w: W, width: u32, height: u32, depth: png::BitDepth, data: &[u8]) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(w, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(depth);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(data)?;
+    writer.finish()?;
+    Ok(())
 }
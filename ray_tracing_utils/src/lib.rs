@@ -1,6 +1,21 @@
 pub mod image;
+pub mod pnm;
+pub mod hdr;
+pub mod voxel;
 pub mod math;
 pub mod color;
+pub mod aabb;
 pub mod hittable;
+pub mod bvh;
 pub mod camera;
+pub mod tile;
+pub mod framebuffer;
+pub mod sampling;
+pub mod environment;
+pub mod render;
+pub mod perlin;
+pub mod texture;
 pub mod material;
+pub mod medium;
+pub mod mesh;
+pub mod scene;
@@ -1,38 +1,98 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
 use crate::math::{Vec3, Ray, Color, minval};
 use crate::hittable::HitRecord;
+use crate::texture::{Texture, SolidColor};
+use crate::sampling;
 use dyn_clone::DynClone;
 
-pub trait Material: DynClone {
+pub trait Material: DynClone + Send + Sync {
     fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)>;
+
+    /// Radiance the surface gives off by itself at the hit point.
+    fn emitted(&self, _ray: &Ray, _rec: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// BSDF times cosine for light arriving from `direction`, and the density
+    /// with which `scatter` picks that direction. `None` for materials that
+    /// cannot be evaluated this way (perfect mirrors, glass), which the
+    /// renderer then never samples lights for.
+    fn scattering(&self, _ray: &Ray, _rec: &HitRecord, _direction: Vec3) -> Option<(Color, f32)> {
+        None
+    }
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Lambertian {
+    pub fn new(albedo: Color) -> Self {
+        Lambertian { albedo: Arc::new(SolidColor::new(albedo)) }
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Self {
+        Lambertian { albedo }
+    }
+}
+
+impl Default for Lambertian {
+    fn default() -> Self {
+        Lambertian::new(Color::default())
+    }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
         let scatter_direction = rec.normal + Vec3::random_unit_vector();
-        let scattered: Ray = Ray::new(rec.p, scatter_direction);
-        let attenuation: Color = self.albedo;
+        let scattered: Ray = Ray::with_time(rec.p, scatter_direction, ray.time);
+        let attenuation: Color = self.albedo.value_at(rec);
 
         Some((scattered, attenuation))
     }
+
+    fn scattering(&self, _ray: &Ray, rec: &HitRecord, direction: Vec3) -> Option<(Color, f32)> {
+        // `scatter` is cosine-weighted, so pdf = cos / pi and f = albedo / pi.
+        let cosine = Vec3::dot(rec.normal, direction.normalized());
+        if cosine <= 0.0 {
+            return Some((Color::default(), 0.0));
+        }
+        let pdf = cosine / PI;
+        Some((self.albedo.value_at(rec) * pdf, pdf))
+    }
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct Metal {
     pub fuzz: f32,
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Metal {
+    pub fn new(albedo: Color, fuzz: f32) -> Self {
+        Metal { fuzz, albedo: Arc::new(SolidColor::new(albedo)) }
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, fuzz: f32) -> Self {
+        Metal { fuzz, albedo }
+    }
+}
+
+impl Default for Metal {
+    fn default() -> Self {
+        Metal::new(Color::default(), 0.0)
+    }
 }
 
 impl Material for Metal {
     fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
         let reflected = reflect(ray.direction.normalized(), rec.normal);
         let fuzziness = self.fuzz * Vec3::random_in_unit_sphere();
-        let scattered = Ray::new(rec.p, reflected + fuzziness);
-        let attenuation = self.albedo;
+        let scattered = Ray::with_time(rec.p, reflected + fuzziness, ray.time);
+        let attenuation = self.albedo.value_at(rec);
 
         if Vec3::dot(scattered.direction, rec.normal) > 0.0 {
             Some((scattered, attenuation))
@@ -66,21 +126,21 @@ impl Material for Dielectric {
 
         if etai_over_etat * sin_theta > 1.0 {
             let reflected = reflect(unit_direction, rec.normal);
-            let scattered = Ray::new(rec.p, reflected);
+            let scattered = Ray::with_time(rec.p, reflected, ray.time);
             return Some((scattered, attenuation));
         }
 
         let reflect_prob = schlick(cos_theta, etai_over_etat);
-        let r: f32 = rand::random();
+        let r: f32 = sampling::random();
         if r < reflect_prob {
             let reflected = reflect(unit_direction, rec.normal);
-            let scattered = Ray::new(rec.p, reflected);
+            let scattered = Ray::with_time(rec.p, reflected, ray.time);
             return Some((scattered, attenuation));
         }
 
         let refracted = refract(unit_direction, rec.normal, etai_over_etat);
-        let scattered = Ray::new(rec.p, refracted);
-        return Some((scattered, attenuation));
+        let scattered = Ray::with_time(rec.p, refracted, ray.time);
+        Some((scattered, attenuation))
     }
 }
 
@@ -96,3 +156,99 @@ pub fn schlick(cosine: f32, ref_idx: f32) -> f32 {
     let r0 = r0 * r0;
     r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
+
+/// Phase function scattering equally in every direction, for use inside a
+/// participating medium. `albedo` is the chance of scattering rather than
+/// being absorbed.
+#[derive(Clone)]
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Isotropic { albedo: Arc::new(SolidColor::new(albedo)) }
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Self {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+        let scattered = Ray::with_time(rec.p, Vec3::random_unit_vector(), ray.time);
+        Some((scattered, self.albedo.value_at(rec)))
+    }
+
+    fn scattering(&self, _ray: &Ray, rec: &HitRecord, _direction: Vec3) -> Option<(Color, f32)> {
+        let pdf = 1.0 / (4.0 * PI);
+        Some((self.albedo.value_at(rec) * pdf, pdf))
+    }
+}
+
+/// Henyey-Greenstein phase function. `g` in `(-1, 1)` is the mean cosine
+/// of the scattering angle: positive values scatter forward, as in haze
+/// and clouds, negative values back, and zero is isotropic.
+#[derive(Clone)]
+pub struct HenyeyGreenstein {
+    pub albedo: Arc<dyn Texture>,
+    pub g: f32,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f32) -> Self {
+        HenyeyGreenstein { albedo: Arc::new(SolidColor::new(albedo)), g }
+    }
+
+    /// Density over the sphere of turning by an angle with cosine `cos_theta`.
+    pub fn phase(g: f32, cos_theta: f32) -> f32 {
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+    }
+
+    /// Cosine of a scattering angle drawn from the phase function for a
+    /// uniform `u` in `[0, 1)`.
+    pub fn sample_cos_theta(g: f32, u: f32) -> f32 {
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * u;
+        }
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+        let forward = ray.direction.normalized();
+        let cos_theta = HenyeyGreenstein::sample_cos_theta(self.g, sampling::random());
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * sampling::random::<f32>();
+        let (u, v) = Vec3::orthonormal_basis(forward);
+        let direction = forward * cos_theta + (u * phi.cos() + v * phi.sin()) * sin_theta;
+        // Sampled in proportion to the phase function, which cancels out.
+        Some((Ray::with_time(rec.p, direction, ray.time), self.albedo.value_at(rec)))
+    }
+
+    fn scattering(&self, ray: &Ray, rec: &HitRecord, direction: Vec3) -> Option<(Color, f32)> {
+        let cos_theta = Vec3::dot(ray.direction.normalized(), direction.normalized());
+        let pdf = HenyeyGreenstein::phase(self.g, cos_theta);
+        Some((self.albedo.value_at(rec) * pdf, pdf))
+    }
+}
+
+/// Light source: emits `emit` from both sides and reflects nothing.
+#[derive(Default, Clone)]
+pub struct DiffuseLight {
+    pub emit: Color,
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: &Ray, _rec: &HitRecord) -> Option<(Ray, Color)> {
+        None
+    }
+
+    fn emitted(&self, _ray: &Ray, _rec: &HitRecord) -> Color {
+        self.emit
+    }
+}
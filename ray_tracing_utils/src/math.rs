@@ -1,5 +1,7 @@
 use std::ops;
-use rand::{thread_rng, Rng};
+use rand::Rng;
+
+use crate::sampling::with_rng;
 
 #[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct Vec3 {
@@ -17,19 +19,11 @@ impl Vec3 {
     }
 
     pub fn random() -> Self {
-        let mut rng = thread_rng();
-        let x: f32 = rng.gen();
-        let y: f32 = rng.gen();
-        let z: f32 = rng.gen();
-        Vec3 { x, y, z }
+        with_rng(|rng| Vec3 { x: rng.gen(), y: rng.gen(), z: rng.gen() })
     }
 
     pub fn random_range(min: f32, max: f32) -> Self {
-        let mut rng = thread_rng();
-        let x: f32 = rng.gen_range(min..max);
-        let y: f32 = rng.gen_range(min..max);
-        let z: f32 = rng.gen_range(min..max);
-        Vec3 { x, y, z }
+        with_rng(|rng| Vec3 { x: rng.gen_range(min..max), y: rng.gen_range(min..max), z: rng.gen_range(min..max) })
     }
 
     pub fn random_in_unit_sphere() -> Self {
@@ -52,11 +46,9 @@ impl Vec3 {
     }
 
     pub fn random_unit_vector() -> Self {
-        let mut rng = thread_rng();
-        let a: f32 = rng.gen_range(0.0..std::f32::consts::PI*2.0);
-        let z: f32 = rng.gen_range(-1.0..1.0);
+        let (a, z): (f32, f32) = with_rng(|rng| (rng.gen_range(0.0..std::f32::consts::PI*2.0), rng.gen_range(-1.0..1.0)));
         let r: f32 = (1.0 - z*z).sqrt();
-        Vec3 { x: r * a.cos(), y: r * a.sin(), z: z }
+        Vec3 { x: r * a.cos(), y: r * a.sin(), z }
     }
 
     pub fn random_in_hemisphere(normal: Self) -> Self {
@@ -91,6 +83,47 @@ impl Vec3 {
             z: u.x * v.y - u.y * v.x,
         }
     }
+
+    pub fn min(u: Self, v: Self) -> Self {
+        Self {
+            x: u.x.min(v.x),
+            y: u.y.min(v.y),
+            z: u.z.min(v.z),
+        }
+    }
+
+    pub fn max(u: Self, v: Self) -> Self {
+        Self {
+            x: u.x.max(v.x),
+            y: u.y.max(v.y),
+            z: u.z.max(v.z),
+        }
+    }
+
+    /// Two unit vectors completing `w` (a unit vector) to an orthonormal basis.
+    pub(crate) fn orthonormal_basis(w: Self) -> (Self, Self) {
+        // Duff et al., "Building an Orthonormal Basis, Revisited".
+        let sign = 1.0f32.copysign(w.z);
+        let a = -1.0 / (sign + w.z);
+        let b = w.x * w.y * a;
+        (
+            Vec3::new(1.0 + sign * w.x * w.x * a, sign * b, -sign * w.x),
+            Vec3::new(b, sign + w.y * w.y * a, -w.y),
+        )
+    }
+}
+
+impl ops::Index<usize> for Vec3 {
+    type Output = f32;
+
+    fn index(&self, axis: usize) -> &f32 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index out of range: {}", axis),
+        }
+    }
 }
 
 impl ops::Add<Vec3> for Vec3 {
@@ -181,11 +214,18 @@ impl ops::Div<f32> for Vec3 {
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    /// Moment within the camera's shutter interval that the ray sees.
+    /// Moving objects are hit where they are at this time.
+    pub time: f32,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Vec3) -> Self {
-        Ray { origin, direction }
+        Ray { origin, direction, time: 0.0 }
+    }
+
+    pub fn with_time(origin: Point3, direction: Vec3, time: f32) -> Self {
+        Ray { origin, direction, time }
     }
 
     pub fn at(self, t: f32) -> Point3 {
@@ -193,6 +233,371 @@ impl Ray {
     }
 }
 
+/// 4x4 matrix, row-major, acting on column vectors.
+pub type Matrix4 = [[f32; 4]; 4];
+
+const IDENTITY: Matrix4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Invertible 4x4 transform. The inverse is computed once on construction,
+/// so moving rays into an object's space costs no more than moving points
+/// out of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform { matrix: IDENTITY, inverse: IDENTITY }
+    }
+
+    /// `None` if `matrix` is singular, as for a zero scale.
+    pub fn from_matrix(matrix: Matrix4) -> Option<Self> {
+        Some(Transform { matrix, inverse: invert(&matrix)? })
+    }
+
+    /// Like `from_matrix`, for a column-major matrix as glTF stores them.
+    pub fn from_columns(columns: Matrix4) -> Option<Self> {
+        Transform::from_matrix(transpose(&columns))
+    }
+
+    pub fn translate(offset: Vec3) -> Self {
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for axis in 0..3 {
+            matrix[axis][3] = offset[axis];
+            inverse[axis][3] = -offset[axis];
+        }
+        Transform { matrix, inverse }
+    }
+
+    /// Panics if a factor is zero.
+    pub fn scale(factors: Vec3) -> Self {
+        assert!(factors.x != 0.0 && factors.y != 0.0 && factors.z != 0.0, "zero scale: {:?}", factors);
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for axis in 0..3 {
+            matrix[axis][axis] = factors[axis];
+            inverse[axis][axis] = 1.0 / factors[axis];
+        }
+        Transform { matrix, inverse }
+    }
+
+    /// Rotation by `angle` radians counter-clockwise about `axis`, looking
+    /// down the axis toward the origin.
+    pub fn rotate(axis: Vec3, angle: f32) -> Self {
+        let a = axis.normalized();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+        let mut matrix = IDENTITY;
+        matrix[0][0] = t * a.x * a.x + cos;
+        matrix[0][1] = t * a.x * a.y - sin * a.z;
+        matrix[0][2] = t * a.x * a.z + sin * a.y;
+        matrix[1][0] = t * a.x * a.y + sin * a.z;
+        matrix[1][1] = t * a.y * a.y + cos;
+        matrix[1][2] = t * a.y * a.z - sin * a.x;
+        matrix[2][0] = t * a.x * a.z - sin * a.y;
+        matrix[2][1] = t * a.y * a.z + sin * a.x;
+        matrix[2][2] = t * a.z * a.z + cos;
+        // Rotations are orthogonal: the inverse is the transpose.
+        Transform { matrix, inverse: transpose(&matrix) }
+    }
+
+    pub fn rotate_x(angle: f32) -> Self {
+        Transform::rotate(Vec3::new(1.0, 0.0, 0.0), angle)
+    }
+
+    pub fn rotate_y(angle: f32) -> Self {
+        Transform::rotate(Vec3::new(0.0, 1.0, 0.0), angle)
+    }
+
+    pub fn rotate_z(angle: f32) -> Self {
+        Transform::rotate(Vec3::new(0.0, 0.0, 1.0), angle)
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(self, next: Transform) -> Self {
+        next * self
+    }
+
+    pub fn inverse(&self) -> Self {
+        Transform { matrix: self.inverse, inverse: self.matrix }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn inverse_matrix(&self) -> &Matrix4 {
+        &self.inverse
+    }
+
+    /// Determinant of the linear part; negative if the transform mirrors.
+    pub fn determinant(&self) -> f32 {
+        let m = &self.matrix;
+        let column = |i: usize| Vec3::new(m[0][i], m[1][i], m[2][i]);
+        Vec3::dot(column(0), Vec3::cross(column(1), column(2)))
+    }
+
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        let m = &self.matrix;
+        let row = |i: usize| m[i][0] * p.x + m[i][1] * p.y + m[i][2] * p.z + m[i][3];
+        let w = row(3);
+        let p = Vec3::new(row(0), row(1), row(2));
+        if w == 1.0 { p } else { p / w }
+    }
+
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.matrix;
+        let row = |i: usize| m[i][0] * v.x + m[i][1] * v.y + m[i][2] * v.z;
+        Vec3::new(row(0), row(1), row(2))
+    }
+
+    /// Normals go through the inverse transpose to stay perpendicular to
+    /// the surface. The result is not normalized.
+    pub fn transform_normal(&self, n: Vec3) -> Vec3 {
+        let m = &self.inverse;
+        let column = |i: usize| m[0][i] * n.x + m[1][i] * n.y + m[2][i] * n.z;
+        Vec3::new(column(0), column(1), column(2))
+    }
+
+    /// The direction is not renormalized, so distances `t` along the ray
+    /// stay the same in both spaces.
+    pub fn transform_ray(&self, ray: &Ray) -> Ray {
+        Ray::with_time(self.transform_point(ray.origin), self.transform_vector(ray.direction), ray.time)
+    }
+}
+
+impl ops::Mul for Transform {
+    type Output = Transform;
+
+    /// Applies `rhs` first, as with the matrices.
+    fn mul(self, rhs: Transform) -> Transform {
+        Transform {
+            matrix: multiply(&self.matrix, &rhs.matrix),
+            inverse: multiply(&rhs.inverse, &self.inverse),
+        }
+    }
+}
+
+/// Transform moving from `start` at `time0` to `end` at `time1`. Each
+/// keyframe is split into scale, rotation and translation, which are
+/// interpolated separately so that a turning object stays rigid; rotations
+/// take the shorter way round, and shear is not kept. Outside the interval
+/// the object rests at the nearer keyframe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimatedTransform {
+    start: Transform,
+    end: Transform,
+    time0: f32,
+    time1: f32,
+    from: Decomposed,
+    to: Decomposed,
+}
+
+impl AnimatedTransform {
+    pub fn new(start: Transform, time0: f32, end: Transform, time1: f32) -> Self {
+        let from = Decomposed::new(&start);
+        let mut to = Decomposed::new(&end);
+        if quaternion_dot(from.rotation, to.rotation) < 0.0 {
+            to.rotation = to.rotation.map(|c| -c);
+        }
+        AnimatedTransform { start, end, time0, time1, from, to }
+    }
+
+    /// Not moving at all.
+    pub fn fixed(transform: Transform) -> Self {
+        AnimatedTransform::new(transform, 0.0, transform, 0.0)
+    }
+
+    pub fn times(&self) -> (f32, f32) {
+        (self.time0, self.time1)
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.start != self.end && self.time1 > self.time0
+    }
+
+    /// Angle in radians that the rotation turns through between the keyframes.
+    pub fn rotation_angle(&self) -> f32 {
+        2.0 * quaternion_dot(self.from.rotation, self.to.rotation).clamp(-1.0, 1.0).acos()
+    }
+
+    pub fn at(&self, time: f32) -> Transform {
+        if !self.is_animated() || time <= self.time0 {
+            return self.start;
+        }
+        if time >= self.time1 {
+            return self.end;
+        }
+        let t = (time - self.time0) / (self.time1 - self.time0);
+        Decomposed {
+            translation: self.from.translation * (1.0 - t) + self.to.translation * t,
+            rotation: slerp(self.from.rotation, self.to.rotation, t),
+            scale: self.from.scale * (1.0 - t) + self.to.scale * t,
+        }.compose()
+    }
+}
+
+/// Affine transform as scale, then rotation (a unit quaternion `w, x, y,
+/// z`), then translation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Decomposed {
+    translation: Vec3,
+    rotation: [f32; 4],
+    scale: Vec3,
+}
+
+impl Decomposed {
+    fn new(transform: &Transform) -> Self {
+        let m = &transform.matrix;
+        let translation = Vec3::new(m[0][3], m[1][3], m[2][3]);
+        let mut columns = [0, 1, 2].map(|i| Vec3::new(m[0][i], m[1][i], m[2][i]));
+        let mut scale = Vec3::new(columns[0].length(), columns[1].length(), columns[2].length());
+        // A mirror goes into the scale, leaving a proper rotation.
+        if transform.determinant() < 0.0 {
+            scale.x = -scale.x;
+        }
+        for (i, column) in columns.iter_mut().enumerate() {
+            *column = *column / scale[i];
+        }
+        let r = |row: usize, col: usize| columns[col][row];
+
+        let trace = r(0, 0) + r(1, 1) + r(2, 2);
+        let rotation = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            [0.25 * s, (r(2, 1) - r(1, 2)) / s, (r(0, 2) - r(2, 0)) / s, (r(1, 0) - r(0, 1)) / s]
+        } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+            let s = (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.0;
+            [(r(2, 1) - r(1, 2)) / s, 0.25 * s, (r(0, 1) + r(1, 0)) / s, (r(0, 2) + r(2, 0)) / s]
+        } else if r(1, 1) > r(2, 2) {
+            let s = (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.0;
+            [(r(0, 2) - r(2, 0)) / s, (r(0, 1) + r(1, 0)) / s, 0.25 * s, (r(1, 2) + r(2, 1)) / s]
+        } else {
+            let s = (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.0;
+            [(r(1, 0) - r(0, 1)) / s, (r(0, 2) + r(2, 0)) / s, (r(1, 2) + r(2, 1)) / s, 0.25 * s]
+        };
+
+        Decomposed { translation, rotation: normalize_quaternion(rotation), scale }
+    }
+
+    fn compose(&self) -> Transform {
+        let [w, x, y, z] = self.rotation;
+        let rotation = [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+        ];
+        let mut matrix = IDENTITY;
+        let mut inverse = IDENTITY;
+        for row in 0..3 {
+            for col in 0..3 {
+                matrix[row][col] = rotation[row][col] * self.scale[col];
+                // (R S)^-1 = S^-1 R^T
+                inverse[row][col] = rotation[col][row] / self.scale[row];
+            }
+            matrix[row][3] = self.translation[row];
+        }
+        for row in inverse.iter_mut().take(3) {
+            row[3] = -(0..3).map(|k| row[k] * self.translation[k]).sum::<f32>();
+        }
+        Transform { matrix, inverse }
+    }
+}
+
+fn quaternion_dot(a: [f32; 4], b: [f32; 4]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize_quaternion(q: [f32; 4]) -> [f32; 4] {
+    let length = quaternion_dot(q, q).sqrt();
+    q.map(|c| c / length)
+}
+
+/// Spherical interpolation between unit quaternions no more than half a
+/// turn apart.
+fn slerp(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    let cos = quaternion_dot(a, b).clamp(-1.0, 1.0);
+    if cos > 0.9995 {
+        // Nearly parallel: a straight blend is as good and stays finite.
+        let mut q = [0.0; 4];
+        for (i, c) in q.iter_mut().enumerate() {
+            *c = a[i] * (1.0 - t) + b[i] * t;
+        }
+        return normalize_quaternion(q);
+    }
+    let theta = cos.acos();
+    let (wa, wb) = (((1.0 - t) * theta).sin() / theta.sin(), (t * theta).sin() / theta.sin());
+    let mut q = [0.0; 4];
+    for (i, c) in q.iter_mut().enumerate() {
+        *c = a[i] * wa + b[i] * wb;
+    }
+    q
+}
+
+fn multiply(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let mut m = [[0.0; 4]; 4];
+    for (row, out) in m.iter_mut().enumerate() {
+        for (col, value) in out.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[row][k] * b[k][col]).sum();
+        }
+    }
+    m
+}
+
+fn transpose(m: &Matrix4) -> Matrix4 {
+    let mut t = [[0.0; 4]; 4];
+    for (row, values) in m.iter().enumerate() {
+        for (col, &value) in values.iter().enumerate() {
+            t[col][row] = value;
+        }
+    }
+    t
+}
+
+/// Gauss-Jordan elimination with partial pivoting, in double precision.
+fn invert(m: &Matrix4) -> Option<Matrix4> {
+    let mut a = m.map(|row| row.map(f64::from));
+    let mut inv = IDENTITY.map(|row| row.map(f64::from));
+    for col in 0..4 {
+        let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col] == 0.0 || !a[pivot][col].is_finite() {
+            return None;
+        }
+        a.swap(col, pivot);
+        inv.swap(col, pivot);
+
+        let scale = 1.0 / a[col][col];
+        for k in 0..4 {
+            a[col][k] *= scale;
+            inv[col][k] *= scale;
+        }
+        for row in 0..4 {
+            let factor = a[row][col];
+            if row == col || factor == 0.0 {
+                continue;
+            }
+            for k in 0..4 {
+                a[row][k] -= factor * a[col][k];
+                inv[row][k] -= factor * inv[col][k];
+            }
+        }
+    }
+    Some(inv.map(|row| row.map(|x| x as f32)))
+}
+
 pub fn minval(x: f32, y: f32) -> f32 {
     if x < y  { x } else { y }
 }
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use crate::math::{Vec3, Point3, Color, Ray};
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::{Material, HenyeyGreenstein};
+use crate::aabb::Aabb;
+use crate::voxel::VoxelGrid;
+use crate::sampling;
+
+/// Fog, smoke or haze of the same density throughout a closed, convex
+/// `boundary`. Rays passing through scatter after a random free-flight
+/// distance, more often the denser the medium, and continue as `phase`
+/// (usually `Isotropic` or `HenyeyGreenstein`) decides.
+pub struct ConstantMedium {
+    pub boundary: Box<dyn Hittable>,
+    /// Extinction coefficient: the chance of an interaction per unit of
+    /// distance travelled.
+    pub density: f32,
+    pub phase: Box<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Box<dyn Hittable>, density: f32, phase: Box<dyn Material>) -> Self {
+        ConstantMedium { boundary, density, phase }
+    }
+}
+
+/// Where a ray is inside a closed, convex `boundary`, as the parameter
+/// range `(enter, exit)` clipped to `t_min..t_max`.
+pub(crate) fn boundary_span(boundary: &dyn Hittable, ray: &Ray, t_min: f32, t_max: f32) -> Option<(f32, f32)> {
+    // Both crossings, even behind the origin, so rays starting inside count.
+    let enter = boundary.hit(ray, f32::NEG_INFINITY, f32::INFINITY)?.t;
+    let exit = boundary.hit(ray, enter + 1e-4, f32::INFINITY)?.t;
+    let (enter, exit) = (enter.max(t_min), exit.min(t_max));
+    (enter < exit).then_some((enter, exit))
+}
+
+/// Record for a scattering event inside a medium, which has no surface:
+/// the normal is arbitrary and only the position and material matter.
+pub(crate) fn medium_record<'a>(ray: &Ray, t: f32, phase: &'a dyn Material) -> HitRecord<'a> {
+    HitRecord {
+        p: ray.at(t),
+        normal: Vec3::new(1.0, 0.0, 0.0),
+        t,
+        u: 0.0,
+        v: 0.0,
+        color: None,
+        front_face: true,
+        material: phase,
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (enter, exit) = boundary_span(self.boundary.as_ref(), ray, t_min, t_max)?;
+
+        let speed = ray.direction.length();
+        let inside = (exit - enter) * speed;
+        // Exponentially distributed free flight; `1 - u` keeps the log finite.
+        let distance = -(1.0 - sampling::random::<f32>()).ln() / self.density;
+        if distance > inside {
+            return None;
+        }
+
+        Some(medium_record(ray, enter + distance / speed, &*self.phase))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f32, t_max: f32) -> f32 {
+        match boundary_span(self.boundary.as_ref(), ray, t_min, t_max) {
+            Some((enter, exit)) => (-self.density * (exit - enter) * ray.direction.length()).exp(),
+            None => 1.0,
+        }
+    }
+}
+
+/// Smoke, fire or clouds whose density varies over a voxel grid, such as
+/// the output of a fluid simulation. Grid values are scaled by `density`
+/// to give the extinction coefficient; of each interaction, the `albedo`
+/// fraction scatters and the rest is absorbed, where the medium glows with
+/// `emission`. Collisions are found by delta tracking and shadow rays are
+/// attenuated by ratio tracking, both against the densest point of the grid.
+pub struct GridMedium {
+    grid: Arc<VoxelGrid>,
+    density: f32,
+    majorant: f32,
+    material: VolumeMaterial,
+}
+
+impl GridMedium {
+    /// Panics unless `density` is finite and non-negative, which keeps the
+    /// majorant usable for tracking.
+    pub fn new(grid: Arc<VoxelGrid>, density: f32, albedo: Color) -> Self {
+        assert!(density.is_finite() && density >= 0.0, "grid medium density {} is not a finite, non-negative value", density);
+        let majorant = density * grid.max_value();
+        let material = VolumeMaterial {
+            phase: HenyeyGreenstein::new(albedo, 0.0),
+            emission: Color::new(0.0, 0.0, 0.0),
+        };
+        GridMedium { grid, density, majorant, material }
+    }
+
+    /// Radiance given off where the medium absorbs light.
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.material.emission = emission;
+        self
+    }
+
+    /// Henyey-Greenstein asymmetry of the scattering; 0 is isotropic.
+    pub fn with_anisotropy(mut self, g: f32) -> Self {
+        self.material.phase.g = g;
+        self
+    }
+
+    pub fn grid(&self) -> &Arc<VoxelGrid> {
+        &self.grid
+    }
+
+    /// Extinction coefficient at `p`.
+    pub fn density_at(&self, p: Point3) -> f32 {
+        self.density * self.grid.sample(p)
+    }
+
+    /// Tentative collisions along `ray`, spaced as in a medium that is
+    /// everywhere as dense as the majorant, up to the end of the grid.
+    fn tentative_collisions<'a>(&'a self, ray: &'a Ray, t_min: f32, t_max: f32) -> impl Iterator<Item = f32> + 'a {
+        let span = if self.majorant > 0.0 { self.grid.bounds().span(ray, t_min, t_max) } else { None };
+        let (mut t, exit) = span.unwrap_or((0.0, 0.0));
+        let rate = self.majorant * ray.direction.length();
+        std::iter::from_fn(move || {
+            t -= (1.0 - sampling::random::<f32>()).ln() / rate;
+            (t < exit).then_some(t)
+        })
+    }
+}
+
+/// Scattering and emission at a collision inside a `GridMedium`.
+#[derive(Clone)]
+struct VolumeMaterial {
+    phase: HenyeyGreenstein,
+    emission: Color,
+}
+
+impl Material for VolumeMaterial {
+    fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<(Ray, Color)> {
+        self.phase.scatter(ray, rec)
+    }
+
+    fn emitted(&self, _ray: &Ray, rec: &HitRecord) -> Color {
+        // The collision absorbs with probability 1 - albedo and then
+        // contributes the emission, which is the collision estimator.
+        let absorbed = Color::new(1.0, 1.0, 1.0) - self.phase.albedo.value_at(rec);
+        self.emission * absorbed
+    }
+
+    fn scattering(&self, ray: &Ray, rec: &HitRecord, direction: Vec3) -> Option<(Color, f32)> {
+        self.phase.scattering(ray, rec, direction)
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        // Delta tracking: a tentative collision is real with probability
+        // density / majorant, and otherwise the ray flies on unchanged.
+        let t = self.tentative_collisions(ray, t_min, t_max)
+            .find(|&t| sampling::random::<f32>() * self.majorant < self.density_at(ray.at(t)))?;
+        Some(medium_record(ray, t, &self.material))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.grid.bounds())
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f32, t_max: f32) -> f32 {
+        // Ratio tracking: weight by the chance of each tentative collision
+        // being null instead of stopping at the first real one.
+        self.tentative_collisions(ray, t_min, t_max)
+            .map(|t| 1.0 - self.density_at(ray.at(t)) / self.majorant)
+            .product()
+    }
+}
@@ -0,0 +1,141 @@
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use crate::math::{Ray, Vec3, Point3, Color};
+use crate::material::Material;
+use crate::aabb::Aabb;
+use crate::bvh::BvhTree;
+use crate::hittable::{HitRecord, Hittable, intersect_triangle, triangle_record};
+
+/// Corners of one mesh triangle as indices into the mesh's buffers. Normals
+/// and surface coordinates are indexed separately from positions, as in OBJ
+/// files, so a position can carry different normals on either side of a crease.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshTriangle {
+    pub positions: [u32; 3],
+    pub normals: Option<[u32; 3]>,
+    pub uvs: Option<[u32; 3]>,
+    pub material: u32,
+}
+
+/// Indexed triangle mesh: shared vertex buffers plus one small record per
+/// triangle, with its own hierarchy over the triangles.
+pub struct TriangleMesh {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f32, f32)>,
+    /// One per position, or empty.
+    colors: Vec<Color>,
+    triangles: Vec<MeshTriangle>,
+    materials: Vec<Box<dyn Material>>,
+    bvh: BvhTree,
+}
+
+impl TriangleMesh {
+    /// Panics if a triangle refers to a vertex, normal, surface coordinate
+    /// or material that is not in the given buffers.
+    pub fn new(
+        positions: Vec<Point3>,
+        normals: Vec<Vec3>,
+        uvs: Vec<(f32, f32)>,
+        triangles: Vec<MeshTriangle>,
+        materials: Vec<Box<dyn Material>>,
+    ) -> Self {
+        for tri in &triangles {
+            assert!(tri.positions.iter().all(|&i| (i as usize) < positions.len()), "position index out of range");
+            assert!(tri.normals.iter().flatten().all(|&i| (i as usize) < normals.len()), "normal index out of range");
+            assert!(tri.uvs.iter().flatten().all(|&i| (i as usize) < uvs.len()), "uv index out of range");
+            assert!((tri.material as usize) < materials.len(), "material index out of range");
+        }
+
+        let bounds: Vec<Aabb> = triangles.iter().map(|tri| {
+            let [a, b, c] = tri.positions.map(|i| positions[i as usize]);
+            Aabb::from_points(a, b).grow(c)
+        }).collect();
+        let bvh = BvhTree::build(&bounds);
+
+        TriangleMesh { positions, normals, uvs, colors: vec![], triangles, materials, bvh }
+    }
+
+    /// Attaches one color per position, reported in `HitRecord::color` and
+    /// picked up by `VertexColorTexture`.
+    pub fn with_colors(mut self, colors: Vec<Color>) -> Self {
+        assert_eq!(colors.len(), self.positions.len(), "need one color per position");
+        self.colors = colors;
+        self
+    }
+
+    /// Replaces every material of the mesh with `material`.
+    pub fn with_material(mut self, material: Box<dyn Material>) -> Self {
+        for tri in &mut self.triangles {
+            tri.material = 0;
+        }
+        self.materials = vec![material];
+        self
+    }
+
+    pub fn positions(&self) -> &[Point3] {
+        &self.positions
+    }
+
+    pub fn normals(&self) -> &[Vec3] {
+        &self.normals
+    }
+
+    pub fn uvs(&self) -> &[(f32, f32)] {
+        &self.uvs
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    pub fn triangles(&self) -> &[MeshTriangle] {
+        &self.triangles
+    }
+
+    pub fn materials(&self) -> &[Box<dyn Material>] {
+        &self.materials
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.bvh.hit(ray, t_min, t_max, |i, closest_so_far| {
+            let tri = &self.triangles[i];
+            let vertices = tri.positions.map(|i| self.positions[i as usize]);
+            let hit = intersect_triangle(ray, &vertices, t_min, closest_so_far)?;
+            let normals = tri.normals.map(|n| n.map(|i| self.normals[i as usize]));
+            let uvs = tri.uvs.map(|uv| uv.map(|i| self.uvs[i as usize]));
+            let colors = (!self.colors.is_empty()).then(|| tri.positions.map(|i| self.colors[i as usize]));
+            Some(triangle_record(
+                ray, hit, &vertices, normals.as_ref(), uvs.as_ref(), colors.as_ref(),
+                &*self.materials[tri.material as usize],
+            ))
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bvh.bounding_box()
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f32, t_max: f32) -> f32 {
+        // Any triangle in the way will do, not just the closest.
+        let mut blocked = false;
+        self.bvh.visit(ray, t_min, t_max, |i| {
+            let vertices = self.triangles[i].positions.map(|i| self.positions[i as usize]);
+            blocked = intersect_triangle(ray, &vertices, t_min, t_max).is_some();
+            !blocked
+        });
+        if blocked { 0.0 } else { 1.0 }
+    }
+}
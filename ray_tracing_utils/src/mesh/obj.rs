@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::math::{Vec3, Point3, Color};
+use crate::material::{Material, Lambertian, Metal, Dielectric, DiffuseLight};
+use crate::texture::{Texture, ImageTexture};
+use crate::mesh::{TriangleMesh, MeshTriangle};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    /// A malformed or unusable line; lines count from 1.
+    Parse { file: String, line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(e) => write!(f, "I/O error: {}", e),
+            ObjError::Parse { file, line, message } => write!(f, "{}:{}: {}", file, line, message),
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ObjError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ObjError {
+    fn from(e: io::Error) -> Self {
+        ObjError::Io(e)
+    }
+}
+
+/// How an MTL material is rendered, see `MtlMaterial::kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtlKind {
+    Diffuse,
+    Metal,
+    Glass,
+    Light,
+}
+
+/// One `newmtl` block of a material library.
+#[derive(Clone)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// `Kd`
+    pub diffuse: Color,
+    /// `Ks`
+    pub specular: Color,
+    /// `Ke`
+    pub emission: Color,
+    /// `Ns`, in `[0, 1000]`.
+    pub shininess: f32,
+    /// `Ni`
+    pub ior: f32,
+    /// `d`, or one minus `Tr`.
+    pub dissolve: f32,
+    pub illum: u32,
+    /// `map_Kd`, which replaces `diffuse` when present. Left empty for image
+    /// formats that cannot be decoded (anything but PNG, HDR and PNM).
+    pub diffuse_map: Option<Arc<dyn Texture>>,
+}
+
+impl MtlMaterial {
+    pub fn new(name: &str) -> Self {
+        MtlMaterial {
+            name: name.to_string(),
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::default(),
+            emission: Color::default(),
+            shininess: 0.0,
+            ior: 1.0,
+            dissolve: 1.0,
+            illum: 2,
+            diffuse_map: None,
+        }
+    }
+
+    /// Any emission makes a light. Transparency (`d` below 1 or `illum` 4,
+    /// 6 or 7) makes glass, `illum 3` or a specular color without a diffuse
+    /// one makes metal, and everything else is diffuse.
+    pub fn kind(&self) -> MtlKind {
+        let black = |c: Color| c.x <= 0.0 && c.y <= 0.0 && c.z <= 0.0;
+        if !black(self.emission) {
+            MtlKind::Light
+        } else if self.dissolve < 1.0 || matches!(self.illum, 4 | 6 | 7) {
+            MtlKind::Glass
+        } else if self.illum == 3 || (!black(self.specular) && black(self.diffuse) && self.diffuse_map.is_none()) {
+            MtlKind::Metal
+        } else {
+            MtlKind::Diffuse
+        }
+    }
+
+    pub fn to_material(&self) -> Box<dyn Material> {
+        match self.kind() {
+            MtlKind::Light => Box::new(DiffuseLight { emit: self.emission }),
+            // Files often leave `Ni` at its default of 1, which would make
+            // the glass invisible.
+            MtlKind::Glass => Box::new(Dielectric { ref_idx: if self.ior > 1.0 { self.ior } else { 1.5 } }),
+            MtlKind::Metal => {
+                // Phong exponent to roughness, as for a Beckmann lobe.
+                let fuzz = (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt();
+                Box::new(Metal::new(self.specular, fuzz))
+            },
+            MtlKind::Diffuse => match &self.diffuse_map {
+                Some(texture) => Box::new(Lambertian::textured(texture.clone())),
+                None => Box::new(Lambertian::new(self.diffuse)),
+            },
+        }
+    }
+}
+
+/// Loads an OBJ file together with the material libraries and textures it
+/// refers to, which are looked up relative to the file that names them.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<TriangleMesh, ObjError> {
+    let path = path.as_ref();
+    let source = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    parse(&source, &path.display().to_string(), Some(dir))
+}
+
+/// Reads OBJ data without material libraries; every face gets the default
+/// light gray diffuse material.
+pub fn read<R: Read>(mut reader: R) -> Result<TriangleMesh, ObjError> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    parse(&String::from_utf8_lossy(&data), "<obj>", None)
+}
+
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<Vec<MtlMaterial>, ObjError> {
+    let path = path.as_ref();
+    let source = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    parse_mtl(&source, &path.display().to_string(), dir)
+}
+
+struct Line<'a> {
+    file: &'a str,
+    number: usize,
+}
+
+impl Line<'_> {
+    fn error(&self, message: impl Into<String>) -> ObjError {
+        ObjError::Parse { file: self.file.to_string(), line: self.number, message: message.into() }
+    }
+
+    fn floats<const N: usize>(&self, keyword: &str, args: &[&str], required: usize) -> Result<[f32; N], ObjError> {
+        if args.len() < required {
+            return Err(self.error(format!("'{}' needs at least {} numbers", keyword, required)));
+        }
+        let mut values = [0.0; N];
+        for (value, arg) in values.iter_mut().zip(args) {
+            *value = arg.parse().map_err(|_| self.error(format!("invalid number {:?}", arg)))?;
+        }
+        Ok(values)
+    }
+
+    fn color(&self, keyword: &str, args: &[&str]) -> Result<Color, ObjError> {
+        // A single value means gray.
+        let [r, g, b] = self.floats(keyword, args, 1)?;
+        Ok(if args.len() < 3 { Color::new(r, r, r) } else { Color::new(r, g, b) })
+    }
+}
+
+/// Splits a line into its keyword and arguments, dropping comments.
+fn tokens(line: &str) -> Option<(&str, Vec<&str>)> {
+    let line = line.split('#').next().unwrap_or("");
+    let mut tokens = line.split_whitespace();
+    let keyword = tokens.next()?;
+    Some((keyword, tokens.collect()))
+}
+
+/// Parses a material library. `file` names it in error messages and `dir`
+/// is where texture paths are resolved.
+pub fn parse_mtl(source: &str, file: &str, dir: &Path) -> Result<Vec<MtlMaterial>, ObjError> {
+    let mut materials: Vec<MtlMaterial> = vec![];
+    for (i, text) in source.lines().enumerate() {
+        let line = Line { file, number: i + 1 };
+        let Some((keyword, args)) = tokens(text) else { continue };
+
+        if keyword == "newmtl" {
+            let name = args.first().ok_or_else(|| line.error("'newmtl' needs a name"))?;
+            materials.push(MtlMaterial::new(name));
+            continue;
+        }
+        let material = match materials.last_mut() {
+            Some(material) => material,
+            None => return Err(line.error(format!("'{}' before any 'newmtl'", keyword))),
+        };
+        match keyword {
+            "Kd" => material.diffuse = line.color(keyword, &args)?,
+            "Ks" => material.specular = line.color(keyword, &args)?,
+            "Ke" => material.emission = line.color(keyword, &args)?,
+            "Ns" => material.shininess = line.floats::<1>(keyword, &args, 1)?[0],
+            "Ni" => material.ior = line.floats::<1>(keyword, &args, 1)?[0],
+            "d" => material.dissolve = line.floats::<1>(keyword, &args, 1)?[0],
+            "Tr" => material.dissolve = 1.0 - line.floats::<1>(keyword, &args, 1)?[0],
+            "illum" => {
+                let arg = args.first().ok_or_else(|| line.error("'illum' needs a number"))?;
+                material.illum = arg.parse().map_err(|_| line.error(format!("invalid illumination model {:?}", arg)))?;
+            },
+            "map_Kd" => {
+                // Options such as `-s 1 1 1` come before the file name.
+                let name = args.last().ok_or_else(|| line.error("'map_Kd' needs a file name"))?;
+                material.diffuse_map = load_texture(&dir.join(name))
+                    .map_err(|e| line.error(format!("cannot load texture {:?}: {}", name, e)))?;
+            },
+            // Ambient terms, other maps and vendor extensions do not affect
+            // any of the crate's materials.
+            _ => (),
+        }
+    }
+    Ok(materials)
+}
+
+/// Texture from a PNG, Radiance HDR or PNM file. Other formats, such as
+/// JPEG, give `None`, so the material falls back to its `Kd`.
+fn load_texture(path: &Path) -> Result<Option<Arc<dyn Texture>>, Box<dyn Error>> {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("").to_ascii_lowercase();
+    match extension.as_str() {
+        "hdr" | "png" | "pbm" | "pgm" | "ppm" | "pnm" => Ok(Some(Arc::new(ImageTexture::from_path(path)?))),
+        _ => Ok(None),
+    }
+}
+
+/// Resolves a 1-based (or negative, relative to the end) OBJ index against
+/// the `count` elements defined so far.
+fn resolve_index(line: &Line, token: &str, count: usize, what: &str) -> Result<u32, ObjError> {
+    let index: i64 = token.parse().map_err(|_| line.error(format!("invalid {} index {:?}", what, token)))?;
+    let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(line.error(format!("{} index {} out of range ({} defined)", what, index, count)));
+    }
+    Ok(resolved as u32)
+}
+
+struct Corner {
+    position: u32,
+    uv: Option<u32>,
+    normal: Option<u32>,
+}
+
+fn parse(source: &str, file: &str, dir: Option<&Path>) -> Result<TriangleMesh, ObjError> {
+    let mut positions: Vec<Point3> = vec![];
+    let mut normals: Vec<Vec3> = vec![];
+    let mut uvs: Vec<(f32, f32)> = vec![];
+    let mut triangles: Vec<MeshTriangle> = vec![];
+
+    let mut library: HashMap<String, MtlMaterial> = HashMap::new();
+    let mut materials: Vec<Box<dyn Material>> = vec![];
+    // Material name to its slot in `materials`; `None` is the default material.
+    let mut slots: HashMap<Option<String>, u32> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for (i, text) in source.lines().enumerate() {
+        let line = Line { file, number: i + 1 };
+        let Some((keyword, args)) = tokens(text) else { continue };
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = line.floats(keyword, &args, 3)?;
+                positions.push(Point3::new(x, y, z));
+            },
+            "vn" => {
+                let [x, y, z] = line.floats(keyword, &args, 3)?;
+                normals.push(Vec3::new(x, y, z));
+            },
+            "vt" => {
+                let [u, v] = line.floats(keyword, &args, 1)?;
+                uvs.push((u, v));
+            },
+            "f" => {
+                if args.len() < 3 {
+                    return Err(line.error("a face needs at least 3 vertices"));
+                }
+                let corners = args.iter().map(|arg| {
+                    let mut parts = arg.split('/');
+                    let position = resolve_index(&line, parts.next().unwrap_or(""), positions.len(), "vertex")?;
+                    let uv = match parts.next() {
+                        Some(t) if !t.is_empty() => Some(resolve_index(&line, t, uvs.len(), "texture coordinate")?),
+                        _ => None,
+                    };
+                    let normal = match parts.next() {
+                        Some(t) if !t.is_empty() => Some(resolve_index(&line, t, normals.len(), "normal")?),
+                        _ => None,
+                    };
+                    if parts.next().is_some() {
+                        return Err(line.error(format!("invalid face vertex {:?}", arg)));
+                    }
+                    Ok(Corner { position, uv, normal })
+                }).collect::<Result<Vec<Corner>, ObjError>>()?;
+
+                let material = match slots.get(&current) {
+                    Some(&slot) => slot,
+                    None => {
+                        let material = match current.as_ref().and_then(|name| library.get(name)) {
+                            Some(mtl) => mtl.to_material(),
+                            None => Box::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+                        };
+                        materials.push(material);
+                        let slot = materials.len() as u32 - 1;
+                        slots.insert(current.clone(), slot);
+                        slot
+                    },
+                };
+
+                // Polygons are split into a fan around their first vertex.
+                for k in 1..corners.len() - 1 {
+                    let tri = [&corners[0], &corners[k], &corners[k + 1]];
+                    let uv = tri.map(|c| c.uv);
+                    let normal = tri.map(|c| c.normal);
+                    triangles.push(MeshTriangle {
+                        positions: tri.map(|c| c.position),
+                        uvs: uv.iter().all(Option::is_some).then(|| uv.map(Option::unwrap)),
+                        normals: normal.iter().all(Option::is_some).then(|| normal.map(Option::unwrap)),
+                        material,
+                    });
+                }
+            },
+            "usemtl" => {
+                let name = args.first().ok_or_else(|| line.error("'usemtl' needs a name"))?;
+                // Unknown names fall back to the default material.
+                current = library.contains_key(*name).then(|| name.to_string());
+            },
+            "mtllib" => {
+                let Some(dir) = dir else { continue };
+                if args.is_empty() {
+                    return Err(line.error("'mtllib' needs a file name"));
+                }
+                for name in &args {
+                    let path = dir.join(name);
+                    let source = fs::read(&path)
+                        .map_err(|e| line.error(format!("cannot read material library {:?}: {}", name, e)))?;
+                    let parsed = parse_mtl(
+                        &String::from_utf8_lossy(&source),
+                        &path.display().to_string(),
+                        path.parent().unwrap_or(dir),
+                    )?;
+                    for mtl in parsed {
+                        library.insert(mtl.name.clone(), mtl);
+                    }
+                }
+            },
+            // Groups, objects, smoothing groups, lines, points and free-form
+            // geometry are not needed for rendering triangles.
+            _ => (),
+        }
+    }
+
+    Ok(TriangleMesh::new(positions, normals, uvs, triangles, materials))
+}
+
+impl TriangleMesh {
+    /// Shorthand for `obj::load`.
+    pub fn from_obj<P: AsRef<Path>>(path: P) -> Result<Self, ObjError> {
+        load(path)
+    }
+}
+
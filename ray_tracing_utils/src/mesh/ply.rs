@@ -0,0 +1,373 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::math::{Vec3, Point3, Color};
+use crate::material::Lambertian;
+use crate::texture::VertexColorTexture;
+use crate::mesh::{TriangleMesh, MeshTriangle};
+use crate::color::decode_gamma;
+
+#[derive(Debug)]
+pub enum PlyError {
+    Io(io::Error),
+    /// The data does not start with `ply`.
+    BadMagic,
+    /// A header line this reader does not understand; lines count from 1.
+    Header { line: usize, message: String },
+    /// Body values that are malformed or refer to missing vertices.
+    InvalidData(String),
+    UnexpectedEof,
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlyError::Io(e) => write!(f, "I/O error: {}", e),
+            PlyError::BadMagic => write!(f, "not a PLY file"),
+            PlyError::Header { line, message } => write!(f, "header line {}: {}", line, message),
+            PlyError::InvalidData(message) => write!(f, "invalid data: {}", message),
+            PlyError::UnexpectedEof => write!(f, "unexpected end of file"),
+        }
+    }
+}
+
+impl Error for PlyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PlyError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PlyError {
+    fn from(e: io::Error) -> Self {
+        PlyError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Scale that maps a color channel of this type to `[0, 1]`.
+    fn color_scale(self) -> f64 {
+        match self {
+            Scalar::U8 => 1.0 / 255.0,
+            Scalar::U16 => 1.0 / 65535.0,
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar { name: String, ty: Scalar },
+    List { name: String, count: Scalar, item: Scalar },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar { name, .. } | Property::List { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    body_start: usize,
+}
+
+fn parse_header(data: &[u8]) -> Result<Header, PlyError> {
+    let mut pos = 0;
+    let mut number = 0;
+    let mut next_line = || -> Result<(usize, &str), PlyError> {
+        let rest = data.get(pos..).filter(|rest| !rest.is_empty()).ok_or(PlyError::UnexpectedEof)?;
+        let len = rest.iter().position(|&c| c == b'\n').ok_or(PlyError::UnexpectedEof)?;
+        pos += len + 1;
+        number += 1;
+        let text = std::str::from_utf8(&rest[..len]).unwrap_or("").trim_end_matches('\r');
+        Ok((number, text))
+    };
+
+    if next_line()?.1.trim() != "ply" {
+        return Err(PlyError::BadMagic);
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    loop {
+        let (line, text) = next_line()?;
+        let error = |message: String| PlyError::Header { line, message };
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        match fields.as_slice() {
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => (),
+            ["format", kind, _version] => {
+                format = Some(match *kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(error(format!("unknown format {:?}", kind))),
+                });
+            },
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| error(format!("invalid element count {:?}", count)))?;
+                elements.push(Element { name: name.to_string(), count, properties: vec![] });
+            },
+            ["property", "list", count, item, name] => {
+                let scalar = |ty: &str| Scalar::from_name(ty).ok_or_else(|| error(format!("unknown type {:?}", ty)));
+                let property = Property::List { name: name.to_string(), count: scalar(count)?, item: scalar(item)? };
+                let element = elements.last_mut().ok_or_else(|| error("property before any element".to_string()))?;
+                element.properties.push(property);
+            },
+            ["property", ty, name] => {
+                let ty = Scalar::from_name(ty).ok_or_else(|| error(format!("unknown type {:?}", ty)))?;
+                let element = elements.last_mut().ok_or_else(|| error("property before any element".to_string()))?;
+                element.properties.push(Property::Scalar { name: name.to_string(), ty });
+            },
+            _ => return Err(error(format!("unrecognized line {:?}", text))),
+        }
+    }
+
+    let format = format.ok_or(PlyError::Header { line: number, message: "missing format line".to_string() })?;
+    Ok(Header { format, elements, body_start: pos })
+}
+
+/// Reads body values one at a time, in text or either byte order.
+struct Body<'a> {
+    format: Format,
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Body<'_> {
+    fn value(&mut self, ty: Scalar) -> Result<f64, PlyError> {
+        if self.format == Format::Ascii {
+            return self.token();
+        }
+        let bytes = self.data.get(self.pos..self.pos + ty.size()).ok_or(PlyError::UnexpectedEof)?;
+        self.pos += ty.size();
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        if self.format == Format::BinaryBigEndian {
+            buf[..bytes.len()].reverse();
+        }
+        // `buf` now holds the value in little-endian order.
+        Ok(match ty {
+            Scalar::I8 => buf[0] as i8 as f64,
+            Scalar::U8 => buf[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(buf),
+        })
+    }
+
+    fn token(&mut self) -> Result<f64, PlyError> {
+        let rest = &self.data[self.pos..];
+        let start = rest.iter().position(|c| !c.is_ascii_whitespace()).ok_or(PlyError::UnexpectedEof)?;
+        let len = rest[start..].iter().position(|c| c.is_ascii_whitespace()).unwrap_or(rest.len() - start);
+        self.pos += start + len;
+        let token = String::from_utf8_lossy(&rest[start..start + len]);
+        token.parse().map_err(|_| PlyError::InvalidData(format!("expected a number, found {:?}", token)))
+    }
+
+    /// Reads one element, returning its scalar properties in order (lists
+    /// count as NaN) and the items of the list named `list`, if any.
+    fn element(&mut self, element: &Element, list: &str, scalars: &mut Vec<f64>, items: &mut Vec<f64>) -> Result<(), PlyError> {
+        scalars.clear();
+        items.clear();
+        for property in &element.properties {
+            match property {
+                Property::Scalar { ty, .. } => scalars.push(self.value(*ty)?),
+                Property::List { name, count, item } => {
+                    scalars.push(f64::NAN);
+                    let n = self.value(*count)?;
+                    if !(0.0..=u32::MAX as f64).contains(&n) {
+                        return Err(PlyError::InvalidData(format!("invalid list length {}", n)));
+                    }
+                    for _ in 0..n as usize {
+                        let value = self.value(*item)?;
+                        if name == list {
+                            items.push(value);
+                        }
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes an ASCII or binary PLY file with a `vertex` and a `face`
+/// element. Normals (`nx ny nz`), surface coordinates (`u v`, `s t` or
+/// `texture_u texture_v`) and colors (`red green blue`) are read when the
+/// vertices have them; colored meshes get a `VertexColorTexture` albedo.
+pub fn decode(data: &[u8]) -> Result<TriangleMesh, PlyError> {
+    let header = parse_header(data)?;
+    let mut body = Body { format: header.format, data, pos: header.body_start };
+
+    let mut positions: Vec<Point3> = vec![];
+    let mut normals: Vec<Vec3> = vec![];
+    let mut uvs: Vec<(f32, f32)> = vec![];
+    let mut colors: Vec<Color> = vec![];
+    let mut faces: Vec<[u32; 3]> = vec![];
+
+    let mut scalars = vec![];
+    let mut items = vec![];
+    for element in &header.elements {
+        let find = |name: &str| element.properties.iter().position(|p| p.name() == name);
+        let find_all = |names: [&str; 3]| -> Option<[usize; 3]> {
+            Some([find(names[0])?, find(names[1])?, find(names[2])?])
+        };
+
+        match element.name.as_str() {
+            "vertex" => {
+                let xyz = find_all(["x", "y", "z"])
+                    .ok_or_else(|| PlyError::InvalidData("vertices need x, y and z".to_string()))?;
+                let nxyz = find_all(["nx", "ny", "nz"]);
+                let uv = [("u", "v"), ("s", "t"), ("texture_u", "texture_v")].iter()
+                    .find_map(|(u, v)| Some([find(u)?, find(v)?]));
+                let rgb = find_all(["red", "green", "blue"]);
+                let rgb_scale = rgb.map(|[r, ..]| match &element.properties[r] {
+                    Property::Scalar { ty, .. } => ty.color_scale(),
+                    Property::List { .. } => 1.0,
+                });
+
+                for _ in 0..element.count {
+                    body.element(element, "", &mut scalars, &mut items)?;
+                    let vec3 = |[x, y, z]: [usize; 3]| Vec3::new(scalars[x] as f32, scalars[y] as f32, scalars[z] as f32);
+                    positions.push(vec3(xyz));
+                    if let Some(n) = nxyz {
+                        normals.push(vec3(n));
+                    }
+                    if let Some([u, v]) = uv {
+                        uvs.push((scalars[u] as f32, scalars[v] as f32));
+                    }
+                    if let (Some(c), Some(scale)) = (rgb, rgb_scale) {
+                        // Gamma 2 encoded, as for 8-bit images.
+                        colors.push(decode_gamma(vec3(c) * scale as f32));
+                    }
+                }
+            },
+            "face" => {
+                let list = ["vertex_indices", "vertex_index"].into_iter()
+                    .find(|name| find(name).is_some())
+                    .ok_or_else(|| PlyError::InvalidData("faces need a vertex_indices list".to_string()))?;
+                for _ in 0..element.count {
+                    body.element(element, list, &mut scalars, &mut items)?;
+                    let mut corners = Vec::with_capacity(items.len());
+                    for &index in &items {
+                        // Faces may come before vertices, so indices are checked at the end.
+                        if index < 0.0 || index.fract() != 0.0 {
+                            return Err(PlyError::InvalidData(format!("invalid vertex index {}", index)));
+                        }
+                        corners.push(index as u32);
+                    }
+                    // Polygons are split into a fan around their first vertex.
+                    for k in 2..corners.len() {
+                        faces.push([corners[0], corners[k - 1], corners[k]]);
+                    }
+                }
+            },
+            // Edges, materials and other elements are skipped.
+            _ => {
+                for _ in 0..element.count {
+                    body.element(element, "", &mut scalars, &mut items)?;
+                }
+            },
+        }
+    }
+
+    if let Some(index) = faces.iter().flatten().find(|&&i| i as usize >= positions.len()) {
+        return Err(PlyError::InvalidData(format!("vertex index {} out of range ({} vertices)", index, positions.len())));
+    }
+
+    let triangles = faces.into_iter().map(|corners| MeshTriangle {
+        positions: corners,
+        normals: (!normals.is_empty()).then_some(corners),
+        uvs: (!uvs.is_empty()).then_some(corners),
+        material: 0,
+    }).collect();
+
+    let gray = Color::new(0.8, 0.8, 0.8);
+    if colors.is_empty() {
+        Ok(TriangleMesh::new(positions, normals, uvs, triangles, vec![Box::new(Lambertian::new(gray))]))
+    } else {
+        let albedo = Arc::new(VertexColorTexture { fallback: gray });
+        let material = Box::new(Lambertian::textured(albedo));
+        Ok(TriangleMesh::new(positions, normals, uvs, triangles, vec![material]).with_colors(colors))
+    }
+}
+
+pub fn read<R: Read>(mut reader: R) -> Result<TriangleMesh, PlyError> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    decode(&data)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<TriangleMesh, PlyError> {
+    read(BufReader::new(File::open(path)?))
+}
+
+impl TriangleMesh {
+    /// Shorthand for `ply::load`.
+    pub fn from_ply<P: AsRef<Path>>(path: P) -> Result<Self, PlyError> {
+        load(path)
+    }
+}
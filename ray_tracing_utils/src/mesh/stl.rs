@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use crate::math::{Point3, Color};
+use crate::material::Lambertian;
+use crate::mesh::{TriangleMesh, MeshTriangle};
+
+#[derive(Debug)]
+pub enum StlError {
+    Io(io::Error),
+    /// A malformed line in an ASCII file; lines count from 1.
+    Parse { line: usize, message: String },
+    /// Neither ASCII STL nor binary STL of the size its triangle count implies.
+    UnrecognizedFormat,
+    /// ASCII data that ends inside a facet.
+    UnexpectedEof,
+}
+
+impl fmt::Display for StlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StlError::Io(e) => write!(f, "I/O error: {}", e),
+            StlError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            StlError::UnrecognizedFormat => write!(f, "not an STL file, or a truncated binary one"),
+            StlError::UnexpectedEof => write!(f, "unexpected end of file"),
+        }
+    }
+}
+
+impl Error for StlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StlError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StlError {
+    fn from(e: io::Error) -> Self {
+        StlError::Io(e)
+    }
+}
+
+const HEADER_SIZE: usize = 84;
+const TRIANGLE_SIZE: usize = 50;
+
+/// Merges corners at identical positions so that STL's separate triangles
+/// become an indexed mesh.
+#[derive(Default)]
+struct Welder {
+    positions: Vec<Point3>,
+    lookup: HashMap<[u32; 3], u32>,
+    triangles: Vec<MeshTriangle>,
+}
+
+impl Welder {
+    fn vertex(&mut self, p: Point3) -> u32 {
+        // Adding zero turns -0.0 into 0.0 so both weld together.
+        let key = [(p.x + 0.0).to_bits(), (p.y + 0.0).to_bits(), (p.z + 0.0).to_bits()];
+        let positions = &mut self.positions;
+        *self.lookup.entry(key).or_insert_with(|| {
+            positions.push(p);
+            positions.len() as u32 - 1
+        })
+    }
+
+    fn triangle(&mut self, corners: [Point3; 3]) {
+        let positions = corners.map(|p| self.vertex(p));
+        self.triangles.push(MeshTriangle { positions, normals: None, uvs: None, material: 0 });
+    }
+
+    fn finish(self) -> TriangleMesh {
+        let material = Box::new(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+        TriangleMesh::new(self.positions, vec![], vec![], self.triangles, vec![material])
+    }
+}
+
+/// Decodes a binary or ASCII STL file. Shared corners are welded into one
+/// vertex. The stored facet normals are ignored, since exporters often get
+/// them wrong; the winding order decides the front face.
+pub fn decode(data: &[u8]) -> Result<TriangleMesh, StlError> {
+    // Binary files may also start with "solid", so the size decides.
+    if data.len() >= HEADER_SIZE {
+        let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+        if data.len() == HEADER_SIZE + count * TRIANGLE_SIZE {
+            return Ok(decode_binary(&data[HEADER_SIZE..], count));
+        }
+    }
+    // Text never holds NUL bytes, while binary headers and coordinates
+    // almost always do.
+    let text = String::from_utf8_lossy(data);
+    if text.trim_start().starts_with("solid") && !data.contains(&0) {
+        decode_ascii(&text)
+    } else {
+        Err(StlError::UnrecognizedFormat)
+    }
+}
+
+fn decode_binary(data: &[u8], count: usize) -> TriangleMesh {
+    let mut welder = Welder::default();
+    for record in data.chunks_exact(TRIANGLE_SIZE).take(count) {
+        let f = |i: usize| f32::from_le_bytes([record[i], record[i + 1], record[i + 2], record[i + 3]]);
+        // Skip the normal, then three corners of three floats each.
+        let corner = |k: usize| Point3::new(f(12 + 12 * k), f(16 + 12 * k), f(20 + 12 * k));
+        welder.triangle([corner(0), corner(1), corner(2)]);
+    }
+    welder.finish()
+}
+
+fn decode_ascii(text: &str) -> Result<TriangleMesh, StlError> {
+    let mut welder = Welder::default();
+    let mut corners: Vec<Point3> = vec![];
+    let mut in_facet = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let error = |message: String| StlError::Parse { line: i + 1, message };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [] => (),
+            ["solid", ..] | ["endsolid", ..] | ["outer", "loop"] | ["endloop"] => (),
+            ["facet", "normal", ..] | ["facet"] => {
+                if in_facet {
+                    return Err(error("facet inside a facet".to_string()));
+                }
+                in_facet = true;
+                corners.clear();
+            },
+            ["vertex", x, y, z] => {
+                if !in_facet {
+                    return Err(error("vertex outside a facet".to_string()));
+                }
+                let parse = |s: &str| s.parse::<f32>().map_err(|_| error(format!("invalid number {:?}", s)));
+                corners.push(Point3::new(parse(x)?, parse(y)?, parse(z)?));
+            },
+            ["endfacet"] => {
+                if !in_facet || corners.len() != 3 {
+                    return Err(error(format!("facet with {} vertices", corners.len())));
+                }
+                welder.triangle([corners[0], corners[1], corners[2]]);
+                in_facet = false;
+            },
+            _ => return Err(error(format!("unrecognized line {:?}", line.trim()))),
+        }
+    }
+    if in_facet {
+        return Err(StlError::UnexpectedEof);
+    }
+    Ok(welder.finish())
+}
+
+pub fn read<R: Read>(mut reader: R) -> Result<TriangleMesh, StlError> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    decode(&data)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<TriangleMesh, StlError> {
+    read(BufReader::new(File::open(path)?))
+}
+
+impl TriangleMesh {
+    /// Shorthand for `stl::load`.
+    pub fn from_stl<P: AsRef<Path>>(path: P) -> Result<Self, StlError> {
+        load(path)
+    }
+}
@@ -0,0 +1,123 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::math::{Vec3, Point3};
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin gradient noise over 3D space. The random gradients and
+/// permutation tables come from a seed, so the same seed always yields the
+/// same noise and therefore reproducible renders.
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Perlin::with_seed(0)
+    }
+}
+
+impl Perlin {
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let gradients = (0..POINT_COUNT).map(|_| {
+            loop {
+                let v = Vec3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                );
+                let len = v.length_squared();
+                if len > 1e-4 && len <= 1.0 {
+                    return v.normalized();
+                }
+            }
+        }).collect();
+
+        let mut permutation = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(&mut rng);
+            p
+        };
+        let perm_x = permutation();
+        let perm_y = permutation();
+        let perm_z = permutation();
+
+        Perlin { gradients, perm_x, perm_y, perm_z }
+    }
+
+    /// Gradient noise in about `[-1, 1]`, zero at every integer lattice point.
+    pub fn noise(&self, p: Point3) -> f32 {
+        let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (u, v, w) = (p.x - fx, p.y - fy, p.z - fz);
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+
+        let mut corners = [[[Vec3::default(); 2]; 2]; 2];
+        for (di, plane) in corners.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let x = self.perm_x[((i + di as i64) & 255) as usize];
+                    let y = self.perm_y[((j + dj as i64) & 255) as usize];
+                    let z = self.perm_z[((k + dk as i64) & 255) as usize];
+                    *corner = self.gradients[x ^ y ^ z];
+                }
+            }
+        }
+
+        // Hermite smoothing hides the lattice.
+        let (uu, vv, ww) = (smooth(u), smooth(v), smooth(w));
+        let mut accum = 0.0;
+        for (di, plane) in corners.iter().enumerate() {
+            for (dj, row) in plane.iter().enumerate() {
+                for (dk, gradient) in row.iter().enumerate() {
+                    let (fi, fj, fk) = (di as f32, dj as f32, dk as f32);
+                    let offset = Vec3::new(u - fi, v - fj, w - fk);
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * Vec3::dot(*gradient, offset);
+                }
+            }
+        }
+        accum
+    }
+
+    /// Sum of `|noise|` over `octaves`, each at double the frequency and half
+    /// the weight of the previous one. Always non-negative.
+    pub fn turbulence(&self, p: Point3, octaves: u32) -> f32 {
+        let mut accum = 0.0;
+        let mut p = p;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            accum += weight * self.noise(p).abs();
+            weight *= 0.5;
+            p = p * 2.0;
+        }
+        accum
+    }
+
+    /// Fractal Brownian motion: signed noise summed over `octaves`, with the
+    /// frequency multiplied by `lacunarity` and the weight by `gain` each step.
+    pub fn fbm(&self, p: Point3, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+        let mut accum = 0.0;
+        let mut p = p;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            accum += weight * self.noise(p);
+            weight *= gain;
+            p = p * lacunarity;
+        }
+        accum
+    }
+}
+
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
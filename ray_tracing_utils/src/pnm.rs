@@ -0,0 +1,289 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::image::{Image, Pixel};
+
+/// Netpbm variant, named after its magic number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PnmFormat {
+    /// P1: ASCII bitmap.
+    P1,
+    /// P2: ASCII graymap.
+    P2,
+    /// P3: ASCII pixmap.
+    P3,
+    /// P4: binary bitmap.
+    P4,
+    /// P5: binary graymap.
+    P5,
+    /// P6: binary pixmap.
+    P6,
+}
+
+impl PnmFormat {
+    fn from_magic(magic: [u8; 2]) -> Option<Self> {
+        match &magic {
+            b"P1" => Some(PnmFormat::P1),
+            b"P2" => Some(PnmFormat::P2),
+            b"P3" => Some(PnmFormat::P3),
+            b"P4" => Some(PnmFormat::P4),
+            b"P5" => Some(PnmFormat::P5),
+            b"P6" => Some(PnmFormat::P6),
+            _ => None,
+        }
+    }
+
+    fn is_bitmap(self) -> bool {
+        matches!(self, PnmFormat::P1 | PnmFormat::P4)
+    }
+}
+
+/// Encoding used when writing a pixmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PpmEncoding {
+    /// P3: one pixel per line as decimal text.
+    #[default]
+    Ascii,
+    /// P6: raw bytes, about a quarter of the size.
+    Binary,
+}
+
+#[derive(Debug)]
+pub enum PnmError {
+    Io(io::Error),
+    /// The file does not start with `P1` .. `P6`.
+    BadMagic([u8; 2]),
+    /// The data ended before the header or raster was complete.
+    UnexpectedEof,
+    /// Something other than a decimal number where one was expected.
+    InvalidNumber { offset: usize, token: String },
+    /// Width, height or maxval outside what the format allows.
+    InvalidHeader(String),
+    /// A sample larger than the declared maxval.
+    SampleOutOfRange { offset: usize, value: u32, maxval: u32 },
+}
+
+impl fmt::Display for PnmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PnmError::Io(e) => write!(f, "I/O error: {}", e),
+            PnmError::BadMagic(magic) => {
+                write!(f, "not a PNM file (magic number {:?})", String::from_utf8_lossy(magic))
+            },
+            PnmError::UnexpectedEof => write!(f, "unexpected end of file"),
+            PnmError::InvalidNumber { offset, token } => {
+                write!(f, "expected a number at byte {}, found {:?}", offset, token)
+            },
+            PnmError::InvalidHeader(msg) => write!(f, "invalid header: {}", msg),
+            PnmError::SampleOutOfRange { offset, value, maxval } => {
+                write!(f, "sample {} at byte {} exceeds maxval {}", value, offset, maxval)
+            },
+        }
+    }
+}
+
+impl Error for PnmError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PnmError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PnmError {
+    fn from(e: io::Error) -> Self {
+        PnmError::Io(e)
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Skips whitespace and `#` comments, which run to the end of the line.
+    fn skip_blank(&mut self) {
+        while let Some(&c) = self.data.get(self.pos) {
+            if c == b'#' {
+                while let Some(&c) = self.data.get(self.pos) {
+                    if c == b'\n' || c == b'\r' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else if c.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<u32, PnmError> {
+        self.skip_blank();
+        let start = self.pos;
+        while self.data.get(self.pos).is_some_and(|c| !c.is_ascii_whitespace() && *c != b'#') {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(PnmError::UnexpectedEof);
+        }
+        let token = &self.data[start..self.pos];
+        std::str::from_utf8(token).ok()
+            .filter(|t| t.bytes().all(|c| c.is_ascii_digit()))
+            .and_then(|t| t.parse::<u32>().ok())
+            .ok_or_else(|| PnmError::InvalidNumber {
+                offset: start,
+                token: String::from_utf8_lossy(token).into_owned(),
+            })
+    }
+
+    /// A single `0` or `1` of a P1 raster, where digits need not be separated.
+    fn bit(&mut self) -> Result<u32, PnmError> {
+        self.skip_blank();
+        match self.data.get(self.pos) {
+            Some(b'0') => { self.pos += 1; Ok(0) },
+            Some(b'1') => { self.pos += 1; Ok(1) },
+            Some(_) => Err(PnmError::InvalidNumber {
+                offset: self.pos,
+                token: (self.data[self.pos] as char).to_string(),
+            }),
+            None => Err(PnmError::UnexpectedEof),
+        }
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], PnmError> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.data.len())
+            .ok_or(PnmError::UnexpectedEof)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+}
+
+/// Decodes any of the P1 .. P6 formats. Samples are rescaled from the
+/// file's maxval to 8 bits; bitmaps map 1 to black and 0 to white.
+pub fn read<R: Read>(mut reader: R) -> Result<Image, PnmError> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    decode(&data)
+}
+
+pub fn decode(data: &[u8]) -> Result<Image, PnmError> {
+    let magic = match data {
+        [a, b, ..] => [*a, *b],
+        _ => return Err(PnmError::UnexpectedEof),
+    };
+    let format = PnmFormat::from_magic(magic).ok_or(PnmError::BadMagic(magic))?;
+    let mut parser = Parser { data, pos: 2 };
+
+    let width = parser.number()?;
+    let height = parser.number()?;
+    if width == 0 || height == 0 {
+        return Err(PnmError::InvalidHeader(format!("image size {}x{}", width, height)));
+    }
+    let maxval = if format.is_bitmap() { 1 } else { parser.number()? };
+    if maxval == 0 || maxval > 65535 {
+        return Err(PnmError::InvalidHeader(format!("maxval {} not in 1..=65535", maxval)));
+    }
+
+    let size = (width as usize).checked_mul(height as usize)
+        .ok_or_else(|| PnmError::InvalidHeader(format!("image size {}x{}", width, height)))?;
+
+    if matches!(format, PnmFormat::P4 | PnmFormat::P5 | PnmFormat::P6) {
+        // Exactly one whitespace byte separates the header from binary data.
+        match data.get(parser.pos) {
+            Some(c) if c.is_ascii_whitespace() => parser.pos += 1,
+            Some(_) => return Err(PnmError::InvalidHeader("no whitespace before raster".to_string())),
+            None => return Err(PnmError::UnexpectedEof),
+        }
+    }
+
+    let scale = |offset: usize, value: u32| -> Result<u8, PnmError> {
+        if value > maxval {
+            return Err(PnmError::SampleOutOfRange { offset, value, maxval });
+        }
+        Ok(((value * 255 + maxval / 2) / maxval) as u8)
+    };
+    let gray = |v: u8| Pixel { r: v, g: v, b: v, a: 0xff };
+    let bit = |b: u32| if b == 1 { gray(0x00) } else { gray(0xff) };
+
+    let mut pixels = Vec::with_capacity(size.min(data.len()));
+    match format {
+        PnmFormat::P1 => {
+            for _ in 0..size {
+                pixels.push(bit(parser.bit()?));
+            }
+        },
+        PnmFormat::P2 => {
+            for _ in 0..size {
+                let offset = parser.pos;
+                pixels.push(gray(scale(offset, parser.number()?)?));
+            }
+        },
+        PnmFormat::P3 => {
+            for _ in 0..size {
+                let offset = parser.pos;
+                let r = scale(offset, parser.number()?)?;
+                let g = scale(offset, parser.number()?)?;
+                let b = scale(offset, parser.number()?)?;
+                pixels.push(Pixel { r, g, b, a: 0xff });
+            }
+        },
+        PnmFormat::P4 => {
+            let row_bytes = (width as usize).div_ceil(8);
+            for _ in 0..height {
+                let row = parser.bytes(row_bytes)?;
+                for x in 0..width as usize {
+                    pixels.push(bit(((row[x / 8] >> (7 - x % 8)) & 1) as u32));
+                }
+            }
+        },
+        PnmFormat::P5 | PnmFormat::P6 => {
+            let channels = if format == PnmFormat::P5 { 1 } else { 3 };
+            let sample_bytes = if maxval < 256 { 1 } else { 2 };
+            let raster_len = size.checked_mul(channels * sample_bytes).ok_or(PnmError::UnexpectedEof)?;
+            let start = parser.pos;
+            let raster = parser.bytes(raster_len)?;
+
+            let mut samples = raster.chunks_exact(sample_bytes).enumerate().map(|(k, s)| {
+                let value = if sample_bytes == 1 { s[0] as u32 } else { u16::from_be_bytes([s[0], s[1]]) as u32 };
+                scale(start + k * sample_bytes, value)
+            });
+            for _ in 0..size {
+                let mut next = || samples.next().unwrap_or(Err(PnmError::UnexpectedEof));
+                if channels == 1 {
+                    pixels.push(gray(next()?));
+                } else {
+                    let (r, g, b) = (next()?, next()?, next()?);
+                    pixels.push(Pixel { r, g, b, a: 0xff });
+                }
+            }
+        },
+    }
+
+    Ok(Image { width, height, data: pixels })
+}
+
+/// Writes an 8-bit pixmap. The alpha channel is dropped.
+pub fn write_ppm<W: Write>(image: &Image, mut w: W, encoding: PpmEncoding) -> io::Result<()> {
+    match encoding {
+        PpmEncoding::Ascii => {
+            writeln!(w, "P3")?;
+            writeln!(w, "{} {}", image.width, image.height)?;
+            writeln!(w, "255")?;
+            for px in image.data.iter() {
+                writeln!(w, "{} {} {}", px.r, px.g, px.b)?;
+            }
+        },
+        PpmEncoding::Binary => {
+            write!(w, "P6\n{} {}\n255\n", image.width, image.height)?;
+            let bytes: Vec<u8> = image.data.iter().flat_map(|px| [px.r, px.g, px.b]).collect();
+            w.write_all(&bytes)?;
+        },
+    }
+    w.flush()
+}
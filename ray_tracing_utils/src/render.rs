@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::math::{Color, Ray};
+use crate::environment::{Environment, GradientEnvironment};
+use crate::hittable::Hittable;
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::tile::{Tile, TileRenderer};
+use crate::sampling::{self, with_rng};
+
+#[derive(Clone)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    pub samples_per_pixel: u32,
+    pub max_depth: u32,
+    /// What rays see when they leave the scene.
+    pub environment: Arc<dyn Environment>,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            width: 384,
+            height: 216,
+            samples_per_pixel: 100,
+            max_depth: 50,
+            environment: Arc::new(GradientEnvironment::sky()),
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+}
+
+/// Path tracer rendering a world seen through a camera into a `Framebuffer`.
+#[derive(Clone, Default)]
+pub struct Renderer {
+    pub settings: RenderSettings,
+    pub tiles: TileRenderer,
+    /// Makes renders reproducible: each pixel's samples are drawn from a
+    /// generator seeded with this, the pixel and its sample count, so the
+    /// result does not depend on threads or tile order.
+    pub seed: Option<u64>,
+}
+
+impl Renderer {
+    pub fn new(settings: RenderSettings) -> Self {
+        Renderer { settings, tiles: TileRenderer::default(), seed: None }
+    }
+
+    pub fn render(&self, camera: &Camera, world: &dyn Hittable) -> Framebuffer {
+        self.render_with_progress(camera, world, |_| ())
+    }
+
+    /// Same as `render`, calling `progress` after each finished tile.
+    pub fn render_with_progress<P>(&self, camera: &Camera, world: &dyn Hittable, progress: P) -> Framebuffer
+    where
+        P: Fn(&Tile) + Sync,
+    {
+        let mut framebuffer = Framebuffer::new(self.settings.width, self.settings.height);
+        self.accumulate(camera, world, &mut framebuffer, progress);
+        framebuffer
+    }
+
+    /// Adds another `samples_per_pixel` samples to every pixel of `framebuffer`,
+    /// which must match the configured resolution.
+    pub fn accumulate<P>(&self, camera: &Camera, world: &dyn Hittable, framebuffer: &mut Framebuffer, progress: P)
+    where
+        P: Fn(&Tile) + Sync,
+    {
+        let RenderSettings { width, height, samples_per_pixel, .. } = self.settings;
+        assert_eq!((framebuffer.width, framebuffer.height), (width, height));
+
+        let sums = self.tiles.render_with_progress(
+            width,
+            height,
+            |x, y| {
+                if let Some(seed) = self.seed {
+                    sampling::reseed(pixel_seed(seed, x, y, framebuffer.count(x, y)));
+                }
+                self.render_pixel(camera, world, x, y)
+            },
+            progress,
+        );
+
+        for (k, sum) in sums.into_iter().enumerate() {
+            let (x, y) = (k as u32 % width, k as u32 / width);
+            framebuffer.add_samples(x, y, sum, samples_per_pixel);
+        }
+    }
+
+    /// Sum of all samples for the pixel at column `x`, row `y` (counted from the top).
+    pub fn render_pixel(&self, camera: &Camera, world: &dyn Hittable, x: u32, y: u32) -> Color {
+        let RenderSettings { width, height, samples_per_pixel, max_depth, .. } = self.settings;
+        let i = (height - 1 - y) as f32;
+        let j = x as f32;
+        let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples_per_pixel {
+            let (j_offset, i_offset): (f32, f32) = with_rng(|rng| (rng.gen(), rng.gen()));
+            let u = (j + j_offset) / (width - 1).max(1) as f32;
+            let v = (i + i_offset) / (height - 1).max(1) as f32;
+            let ray = camera.get_ray(u, v);
+            pixel_color = pixel_color + self.ray_color(&ray, world, max_depth);
+        }
+        pixel_color
+    }
+
+    /// Radiance arriving along `ray`, following at most `depth` bounces.
+    ///
+    /// At diffuse surfaces an environment that supports importance sampling
+    /// is also sampled directly with a shadow ray, dimmed by any media it
+    /// passes through; both that and the scattered ray are weighted with the
+    /// power heuristic so neither strategy counts the environment twice.
+    pub fn ray_color(&self, ray: &Ray, world: &dyn Hittable, depth: u32) -> Color {
+        let environment = &self.settings.environment;
+
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = *ray;
+        // Density of the current ray's direction, if the environment was
+        // also sampled directly at the surface it left from.
+        let mut scatter_pdf: Option<f32> = None;
+
+        for bounce in 0..depth {
+            let rec = match world.hit(&ray, 0.001, f32::INFINITY) {
+                Some(rec) => rec,
+                None => {
+                    let mut color = environment.color(ray.direction);
+                    if let Some(pdf) = scatter_pdf {
+                        color = color * power_heuristic(pdf, environment.pdf(ray.direction));
+                    }
+                    return radiance + throughput * color;
+                },
+            };
+
+            radiance = radiance + throughput * rec.material.emitted(&ray, &rec);
+
+            let (scattered, attenuation) = match rec.material.scatter(&ray, &rec) {
+                Some(s) => s,
+                None => return radiance,
+            };
+
+            scatter_pdf = None;
+            // Only worth it if the scattered ray is traced as well.
+            if bounce + 1 < depth {
+                if let Some((direction, color, light_pdf)) = environment.sample() {
+                    let scattering = rec.material.scattering(&ray, &rec, direction);
+                    if let Some((f, pdf)) = scattering {
+                        let shadow_ray = Ray::with_time(rec.p, direction, ray.time);
+                        if light_pdf > 0.0 {
+                            let visibility = world.transmittance(&shadow_ray, 0.001, f32::INFINITY);
+                            let weight = power_heuristic(light_pdf, pdf) / light_pdf;
+                            radiance = radiance + throughput * f * color * (weight * visibility);
+                        }
+                        scatter_pdf = rec.material.scattering(&ray, &rec, scattered.direction).map(|(_, pdf)| pdf);
+                    }
+                }
+            }
+
+            throughput = throughput * attenuation;
+            ray = scattered;
+        }
+
+        radiance
+    }
+}
+
+/// Multiple importance sampling weight for a sample taken with density
+/// `pdf`, when another strategy could have produced it with `other_pdf`.
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 { 0.0 } else { a / (a + b) }
+}
+
+/// Seed for the samples of pixel (`x`, `y`) that follow the first `count`.
+fn pixel_seed(seed: u64, x: u32, y: u32, count: u32) -> u64 {
+    // SplitMix64 steps, so neighbouring pixels get unrelated streams.
+    [x, y, count].iter().fold(seed, |h, &v| {
+        let mut z = (h ^ v as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    })
+}
@@ -0,0 +1,128 @@
+use std::cell::RefCell;
+
+use rand::distributions::{Distribution, Standard};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Runs `f` with the calling thread's sampling generator, which every random
+/// decision made while rendering draws from.
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/// A uniform sample from the sampling generator, like `rand::random`.
+pub fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    with_rng(|rng| rng.gen())
+}
+
+/// Restarts the calling thread's sampling generator from `seed`.
+pub fn reseed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Piecewise-constant distribution over `[0, 1)`, one step per entry of
+/// the function it was built from.
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    // Integral of `func`, which differs from `integral()` when an all-zero
+    // function was replaced by a uniform one.
+    func_integral: f32,
+    integral: f32,
+}
+
+impl Distribution1D {
+    /// Negative values count as zero. An all-zero function is sampled uniformly.
+    pub fn new(func: &[f32]) -> Self {
+        assert!(!func.is_empty(), "distribution needs at least one value");
+        let n = func.len();
+        let mut func: Vec<f32> = func.iter().map(|&f| if f > 0.0 { f } else { 0.0 }).collect();
+
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i] / n as f32;
+        }
+        let integral = cdf[n];
+        let func_integral = if integral > 0.0 && integral.is_finite() {
+            for c in cdf.iter_mut() {
+                *c /= integral;
+            }
+            integral
+        } else {
+            func.fill(1.0);
+            for (i, c) in cdf.iter_mut().enumerate() {
+                *c = i as f32 / n as f32;
+            }
+            1.0
+        };
+
+        Distribution1D { func, cdf, func_integral, integral }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// Mean value of the function the distribution was built from.
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Maps a uniform `u` in `[0, 1)` to `(x, pdf, index)` where `x` lies in step `index`.
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        // Last index whose cdf is <= u, skipping zero-width steps.
+        let index = self.cdf.partition_point(|&c| c <= u).saturating_sub(1).min(self.count() - 1);
+
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let du = if width > 0.0 { (u - self.cdf[index]) / width } else { 0.0 };
+        let x = ((index as f32 + du) / self.count() as f32).min(1.0 - f32::EPSILON);
+
+        (x, self.func[index] / self.func_integral, index)
+    }
+
+    /// Density at `x` in `[0, 1]`.
+    pub fn pdf(&self, x: f32) -> f32 {
+        let index = ((x * self.count() as f32) as usize).min(self.count() - 1);
+        self.func[index] / self.func_integral
+    }
+}
+
+/// Piecewise-constant distribution over `[0, 1)^2` built from a row-major
+/// grid of `width` x `height` values: a marginal over rows and a
+/// conditional distribution within each row.
+#[derive(Debug, Clone)]
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f32], width: usize, height: usize) -> Self {
+        assert_eq!(func.len(), width * height);
+        let conditional: Vec<Distribution1D> = func.chunks_exact(width).map(Distribution1D::new).collect();
+        let row_integrals: Vec<f32> = conditional.iter().map(|d| d.integral()).collect();
+        let marginal = Distribution1D::new(&row_integrals);
+        Distribution2D { conditional, marginal }
+    }
+
+    /// Maps two uniform numbers to a point `(u, v)` and its density.
+    pub fn sample(&self, u0: f32, u1: f32) -> ((f32, f32), f32) {
+        let (v, pdf_v, row) = self.marginal.sample(u1);
+        let (u, pdf_u, _) = self.conditional[row].sample(u0);
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    pub fn pdf(&self, u: f32, v: f32) -> f32 {
+        let rows = self.conditional.len();
+        let row = ((v * rows as f32) as usize).min(rows - 1);
+        self.conditional[row].pdf(u) * self.marginal.pdf(v)
+    }
+}
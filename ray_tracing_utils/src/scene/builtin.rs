@@ -0,0 +1,507 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::math::{Vec3, Point3, Color, Transform};
+use crate::hittable::{Sphere, MovingSphere, Plane, Quad, Cuboid, Instance, Hittable, HittableList};
+use crate::bvh::BvhNode;
+use crate::camera::Camera;
+use crate::environment::{GradientEnvironment, UniformEnvironment};
+use crate::render::RenderSettings;
+use crate::material::{Material, Lambertian, Metal, Dielectric, DiffuseLight, Isotropic};
+use crate::medium::{ConstantMedium, GridMedium};
+use crate::perlin::Perlin;
+use crate::voxel::VoxelGrid;
+use crate::aabb::Aabb;
+use crate::texture::{
+    Texture, CheckerTexture, ImageTexture, NoiseTexture, TurbulenceTexture, MarbleTexture, WoodTexture,
+};
+
+use super::Scene;
+
+/// Names accepted by `by_name`.
+pub const NAMES: &[&str] = &["random", "bouncing", "cornell", "smoke", "materials", "caustics", "dof", "textures", "cloud"];
+
+/// Built-in scene called `name`. `seed` drives the random layout and the
+/// procedural textures of the scenes that have them.
+pub fn by_name(name: &str, seed: u64) -> Option<Scene> {
+    match name {
+        "random" => Some(random_scene(seed)),
+        "bouncing" => Some(bouncing_spheres(seed)),
+        "cornell" => Some(cornell_box()),
+        "smoke" => Some(cornell_smoke()),
+        "materials" => Some(material_spheres()),
+        "caustics" => Some(glass_caustics()),
+        "dof" => Some(depth_of_field()),
+        "textures" => Some(texture_test(seed)),
+        "cloud" => Some(voxel_cloud(seed)),
+        _ => None,
+    }
+}
+
+/// The cover of the book: a field of small random spheres around three
+/// large ones.
+pub fn random_scene(seed: u64) -> Scene {
+    random_spheres(seed, false)
+}
+
+/// The cover with the small diffuse spheres bouncing up while the shutter
+/// is open, for motion blur.
+pub fn bouncing_spheres(seed: u64) -> Scene {
+    random_spheres(seed, true)
+}
+
+fn random_spheres(seed: u64, bouncing: bool) -> Scene {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut hittables: Vec<Box<dyn Hittable>> = vec![];
+    hittables.push(Box::new(Plane::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+
+    for i in -11..=11 {
+        for j in -11..=11 {
+            let choose_material: f32 = rng.gen();
+            let center = Point3::new(
+                i as f32 + 0.9 * rng.gen::<f32>(),
+                0.2,
+                j as f32 + 0.9 * rng.gen::<f32>(),
+            );
+            if (center - Vec3::new(4.0, 0.2, 0.0)).length() <= 0.9 {
+                continue;
+            }
+
+            let diffuse = choose_material < 0.8;
+            let material: Box<dyn Material> = if diffuse {
+                let albedo = Color::new(rng.gen(), rng.gen(), rng.gen());
+                Box::new(Lambertian::new(albedo))
+            } else if choose_material < 0.95 {
+                let albedo = Color::new(
+                    rng.gen_range(0.5..1.0),
+                    rng.gen_range(0.5..1.0),
+                    rng.gen_range(0.5..1.0),
+                );
+                Box::new(Metal::new(albedo, rng.gen_range(0.0..0.5)))
+            } else {
+                Box::new(Dielectric { ref_idx: 1.5 })
+            };
+            if bouncing && diffuse {
+                let center1 = center + Vec3::new(0.0, rng.gen_range(0.0..0.5), 0.0);
+                hittables.push(Box::new(MovingSphere {
+                    center0: center,
+                    center1,
+                    time0: 0.0,
+                    time1: 1.0,
+                    radius: 0.2,
+                    material,
+                }));
+            } else {
+                hittables.push(Box::new(Sphere { center, radius: 0.2, material }));
+            }
+        }
+    }
+
+    hittables.push(Box::new(Sphere {
+        center: Point3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Dielectric { ref_idx: 1.5 }),
+    }));
+    hittables.push(Box::new(Sphere {
+        center: Point3::new(-4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Lambertian::new(Color::new(0.4, 0.2, 0.1))),
+    }));
+    hittables.push(Box::new(Sphere {
+        center: Point3::new(4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)),
+    }));
+
+    let settings = RenderSettings {
+        width: 384,
+        height: 216,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        environment: Arc::new(GradientEnvironment::sky()),
+    };
+    let camera = Camera::new(
+        Point3::new(13.0, 2.0, 3.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        std::f32::consts::PI / 9.0,
+        settings.aspect_ratio(),
+        0.1,
+        10.0,
+    );
+    let camera = if bouncing { camera.with_shutter(0.0, 1.0) } else { camera };
+
+    Scene {
+        world: Box::new(BvhNode::new(HittableList { hittables })),
+        camera,
+        settings,
+    }
+}
+
+/// The Cornell box: red and green side walls, a square ceiling light and
+/// two rotated white blocks, in the original 555 unit cube.
+pub fn cornell_box() -> Scene {
+    let white = Lambertian::new(Color::new(0.73, 0.73, 0.73));
+    let mut hittables = cornell_room(
+        Point3::new(213.0, 554.0, 227.0),
+        Vec3::new(130.0, 0.0, 105.0),
+        Color::new(15.0, 15.0, 15.0),
+    );
+    let tall = block(Vec3::new(165.0, 330.0, 165.0), 15.0, Point3::new(265.0, 0.0, 295.0), Box::new(white.clone()));
+    let short = block(Vec3::new(165.0, 165.0, 165.0), -18.0, Point3::new(130.0, 0.0, 65.0), Box::new(white));
+    hittables.push(Box::new(tall));
+    hittables.push(Box::new(short));
+    cornell_scene(hittables)
+}
+
+/// The Cornell box with the blocks made of dark and light smoke, under a
+/// larger, dimmer light.
+pub fn cornell_smoke() -> Scene {
+    let mut hittables = cornell_room(
+        Point3::new(113.0, 554.0, 127.0),
+        Vec3::new(330.0, 0.0, 305.0),
+        Color::new(7.0, 7.0, 7.0),
+    );
+    let blocks = [
+        (Vec3::new(165.0, 330.0, 165.0), 15.0, Point3::new(265.0, 0.0, 295.0), Color::new(0.0, 0.0, 0.0)),
+        (Vec3::new(165.0, 165.0, 165.0), -18.0, Point3::new(130.0, 0.0, 65.0), Color::new(1.0, 1.0, 1.0)),
+    ];
+    for (size, degrees, offset, albedo) in blocks {
+        // The block only bounds the smoke; its material is never seen.
+        let boundary = block(size, degrees, offset, Box::new(Lambertian::default()));
+        hittables.push(Box::new(ConstantMedium::new(
+            Box::new(boundary),
+            0.01,
+            Box::new(Isotropic::new(albedo)),
+        )));
+    }
+    cornell_scene(hittables)
+}
+
+/// Walls of the Cornell box and a ceiling light spanning `light_size` in
+/// x and z from `light_corner`.
+fn cornell_room(light_corner: Point3, light_size: Vec3, emit: Color) -> Vec<Box<dyn Hittable>> {
+    let red = Lambertian::new(Color::new(0.65, 0.05, 0.05));
+    let white = Lambertian::new(Color::new(0.73, 0.73, 0.73));
+    let green = Lambertian::new(Color::new(0.12, 0.45, 0.15));
+
+    let mut hittables: Vec<Box<dyn Hittable>> = vec![];
+    let side = 555.0;
+    let (x, y, z) = (Vec3::new(side, 0.0, 0.0), Vec3::new(0.0, side, 0.0), Vec3::new(0.0, 0.0, side));
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    push_quad(&mut hittables, x, z, y, &green);
+    push_quad(&mut hittables, origin, y, z, &red);
+    push_quad(&mut hittables, origin, z, x, &white);
+    push_quad(&mut hittables, y, x, z, &white);
+    push_quad(&mut hittables, z, y, x, &white);
+    push_quad(
+        &mut hittables,
+        light_corner,
+        Vec3::new(light_size.x, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, light_size.z),
+        &DiffuseLight { emit },
+    );
+    hittables
+}
+
+fn cornell_scene(hittables: Vec<Box<dyn Hittable>>) -> Scene {
+    let settings = RenderSettings {
+        width: 400,
+        height: 400,
+        samples_per_pixel: 200,
+        max_depth: 50,
+        environment: Arc::new(UniformEnvironment::default()),
+    };
+    let camera = Camera::new(
+        Point3::new(278.0, 278.0, -800.0),
+        Point3::new(278.0, 278.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40f32.to_radians(),
+        settings.aspect_ratio(),
+        0.0,
+        10.0,
+    );
+
+    Scene { world: Box::new(BvhNode::new(HittableList { hittables })), camera, settings }
+}
+
+/// A row of spheres on a checkered floor, one per material: diffuse,
+/// brushed metal, polished gold, glass and a hollow glass bubble.
+pub fn material_spheres() -> Scene {
+    let mut hittables: Vec<Box<dyn Hittable>> = vec![];
+    let floor = Lambertian::textured(Arc::new(
+        CheckerTexture::new(Color::new(0.2, 0.2, 0.2), Color::new(0.8, 0.8, 0.8), 1.0),
+    ));
+    push_floor(&mut hittables, 20.0, &floor);
+
+    let materials: [Box<dyn Material>; 4] = [
+        Box::new(Lambertian::new(Color::new(0.7, 0.3, 0.3))),
+        Box::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.3)),
+        Box::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.0)),
+        Box::new(Dielectric { ref_idx: 1.5 }),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        hittables.push(Box::new(Sphere {
+            center: Point3::new(-4.4 + 2.2 * i as f32, 1.0, 0.0),
+            radius: 1.0,
+            material,
+        }));
+    }
+    // A negative radius flips the normals, turning the inner sphere into an
+    // air pocket inside the outer one.
+    let bubble = Point3::new(4.4, 1.0, 0.0);
+    hittables.push(Box::new(Sphere { center: bubble, radius: 1.0, material: Box::new(Dielectric { ref_idx: 1.5 }) }));
+    hittables.push(Box::new(Sphere { center: bubble, radius: -0.9, material: Box::new(Dielectric { ref_idx: 1.5 }) }));
+
+    let settings = RenderSettings {
+        width: 480,
+        height: 200,
+        samples_per_pixel: 200,
+        max_depth: 50,
+        environment: Arc::new(GradientEnvironment::sky()),
+    };
+    let camera = Camera::new(
+        Point3::new(0.0, 3.0, 12.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        30f32.to_radians(),
+        settings.aspect_ratio(),
+        0.0,
+        12.0,
+    );
+
+    Scene { world: Box::new(BvhNode::new(HittableList { hittables })), camera, settings }
+}
+
+/// Glass spheres on a white floor in a dark room, lit from the side by one
+/// small light so they focus it into caustics. Converges slowly, which is
+/// the point: it shows how well paths through glass reach the light.
+pub fn glass_caustics() -> Scene {
+    let mut hittables: Vec<Box<dyn Hittable>> = vec![];
+    push_floor(&mut hittables, 20.0, &Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+
+    hittables.push(Box::new(Sphere {
+        center: Point3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Dielectric { ref_idx: 1.5 }),
+    }));
+    hittables.push(Box::new(Sphere {
+        center: Point3::new(2.2, 0.6, 1.0),
+        radius: 0.6,
+        material: Box::new(Dielectric { ref_idx: 2.4 }),
+    }));
+    hittables.push(Box::new(Sphere {
+        center: Point3::new(-5.0, 5.0, -1.0),
+        radius: 1.0,
+        material: Box::new(DiffuseLight { emit: Color::new(30.0, 28.0, 25.0) }),
+    }));
+
+    let settings = RenderSettings {
+        width: 400,
+        height: 300,
+        samples_per_pixel: 1000,
+        max_depth: 50,
+        environment: Arc::new(UniformEnvironment { color: Color::new(0.01, 0.01, 0.01) }),
+    };
+    let camera = Camera::new(
+        Point3::new(2.0, 4.0, 9.0),
+        Point3::new(0.5, 0.6, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        35f32.to_radians(),
+        settings.aspect_ratio(),
+        0.0,
+        10.0,
+    );
+
+    Scene { world: Box::new(BvhNode::new(HittableList { hittables })), camera, settings }
+}
+
+/// Spheres receding from the camera, focused on the third one with a wide
+/// aperture, so blur grows with distance in front of and behind it.
+pub fn depth_of_field() -> Scene {
+    let mut hittables: Vec<Box<dyn Hittable>> = vec![];
+    let floor = Lambertian::textured(Arc::new(
+        CheckerTexture::new(Color::new(0.25, 0.25, 0.3), Color::new(0.85, 0.85, 0.85), 0.5),
+    ));
+    push_floor(&mut hittables, 40.0, &floor);
+
+    let colors = [Color::new(0.8, 0.2, 0.2), Color::new(0.2, 0.6, 0.8), Color::new(0.9, 0.7, 0.2)];
+    let centers: Vec<Point3> = (0..7).map(|i| Point3::new(i as f32 - 2.5, 0.5, -2.0 * i as f32)).collect();
+    for (i, &center) in centers.iter().enumerate() {
+        hittables.push(Box::new(Sphere {
+            center,
+            radius: 0.5,
+            material: Box::new(Lambertian::new(colors[i % colors.len()])),
+        }));
+    }
+
+    let settings = RenderSettings {
+        width: 400,
+        height: 225,
+        samples_per_pixel: 200,
+        max_depth: 50,
+        environment: Arc::new(GradientEnvironment::sky()),
+    };
+    let lookfrom = Point3::new(0.0, 1.5, 6.0);
+    let camera = Camera::new(
+        lookfrom,
+        centers[2],
+        Vec3::new(0.0, 1.0, 0.0),
+        30f32.to_radians(),
+        settings.aspect_ratio(),
+        0.6,
+        (centers[2] - lookfrom).length(),
+    );
+
+    Scene { world: Box::new(BvhNode::new(HittableList { hittables })), camera, settings }
+}
+
+/// One sphere per texture: a UV grid image, smooth noise, turbulence,
+/// marble and wood, on a checkered floor. `seed` picks the noise.
+pub fn texture_test(seed: u64) -> Scene {
+    let mut hittables: Vec<Box<dyn Hittable>> = vec![];
+    let floor = Lambertian::textured(Arc::new(
+        CheckerTexture::new(Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9), 1.0),
+    ));
+    push_floor(&mut hittables, 20.0, &floor);
+
+    let textures: [Arc<dyn Texture>; 5] = [
+        Arc::new(uv_grid()),
+        Arc::new(NoiseTexture::new(seed, 4.0)),
+        Arc::new(TurbulenceTexture::new(seed, 4.0)),
+        Arc::new(MarbleTexture::new(seed, 4.0)),
+        Arc::new(WoodTexture::new(seed, 4.0)),
+    ];
+    for (i, texture) in textures.into_iter().enumerate() {
+        hittables.push(Box::new(Sphere {
+            center: Point3::new(-4.4 + 2.2 * i as f32, 1.0, 0.0),
+            radius: 1.0,
+            material: Box::new(Lambertian::textured(texture)),
+        }));
+    }
+
+    let settings = RenderSettings {
+        width: 480,
+        height: 200,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        environment: Arc::new(GradientEnvironment::sky()),
+    };
+    let camera = Camera::new(
+        Point3::new(0.0, 3.0, 12.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        30f32.to_radians(),
+        settings.aspect_ratio(),
+        0.0,
+        12.0,
+    );
+
+    Scene { world: Box::new(BvhNode::new(HittableList { hittables })), camera, settings }
+}
+
+/// A white cloud and a smouldering fireball, each a voxel grid of noisy
+/// density, floating over a checkered floor under the sky. `seed` picks
+/// the noise.
+pub fn voxel_cloud(seed: u64) -> Scene {
+    let mut hittables: Vec<Box<dyn Hittable>> = vec![];
+    let floor = Lambertian::textured(Arc::new(
+        CheckerTexture::new(Color::new(0.2, 0.2, 0.2), Color::new(0.8, 0.8, 0.8), 1.0),
+    ));
+    push_floor(&mut hittables, 20.0, &floor);
+
+    let cloud = noise_ball(seed, Point3::new(-1.2, 2.0, 0.0), Vec3::new(2.4, 1.2, 1.6));
+    hittables.push(Box::new(GridMedium::new(Arc::new(cloud), 10.0, Color::new(0.95, 0.95, 0.95)).with_anisotropy(0.4)));
+
+    let fireball = noise_ball(seed.wrapping_add(1), Point3::new(2.0, 1.2, 0.5), Vec3::new(1.0, 1.0, 1.0));
+    hittables.push(Box::new(
+        GridMedium::new(Arc::new(fireball), 8.0, Color::new(0.3, 0.3, 0.3))
+            .with_emission(Color::new(4.0, 1.4, 0.3)),
+    ));
+
+    let settings = RenderSettings {
+        width: 480,
+        height: 270,
+        samples_per_pixel: 100,
+        max_depth: 50,
+        environment: Arc::new(GradientEnvironment::sky()),
+    };
+    let camera = Camera::new(
+        Point3::new(0.0, 2.5, 9.0),
+        Point3::new(0.0, 1.6, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40f32.to_radians(),
+        settings.aspect_ratio(),
+        0.0,
+        9.0,
+    );
+
+    Scene { world: Box::new(BvhNode::new(HittableList { hittables })), camera, settings }
+}
+
+/// Density falling off from 1 at `center` to 0 at the ellipsoid with
+/// `radii`, roughened with fractal noise, on a 48^3 grid around it.
+fn noise_ball(seed: u64, center: Point3, radii: Vec3) -> VoxelGrid {
+    let perlin = Perlin::with_seed(seed);
+    let bounds = Aabb::new(center - radii, center + radii);
+    VoxelGrid::from_fn([48, 48, 48], bounds, |p| {
+        let d = p - center;
+        let r = Vec3::new(d.x / radii.x, d.y / radii.y, d.z / radii.z).length();
+        let noise = perlin.fbm(p * 2.0, 4, 2.0, 0.5);
+        (1.0 - r + 0.5 * noise).clamp(0.0, 1.0)
+    })
+}
+
+/// 8 x 4 grid of cells whose red and green rise with `u` and `v`, so the
+/// surface coordinates can be read off a render.
+fn uv_grid() -> ImageTexture {
+    let (width, height) = (8, 4);
+    let mut texels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let u = (x as f32 + 0.5) / width as f32;
+            let v = 1.0 - (y as f32 + 0.5) / height as f32;
+            let blue = if (x + y) % 2 == 0 { 0.6 } else { 0.1 };
+            texels.push(Color::new(u, v, blue));
+        }
+    }
+    ImageTexture::new(width as u32, height as u32, texels)
+}
+
+/// Parallelogram with corners `q`, `q + u`, `q + u + v` and `q + v`, facing
+/// along `u x v`, with `u`, `v` as surface coordinates.
+fn push_quad<M>(hittables: &mut Vec<Box<dyn Hittable>>, q: Point3, u: Vec3, v: Vec3, material: &M)
+where
+    M: Material + Clone + 'static,
+{
+    hittables.push(Box::new(Quad::new(q, u, v, Box::new(material.clone()))));
+}
+
+/// Square floor at `y = 0`, `size` wide and centered on the origin.
+fn push_floor<M>(hittables: &mut Vec<Box<dyn Hittable>>, size: f32, material: &M)
+where
+    M: Material + Clone + 'static,
+{
+    let half = size / 2.0;
+    push_quad(
+        hittables,
+        Point3::new(-half, 0.0, -half),
+        Vec3::new(0.0, 0.0, size),
+        Vec3::new(size, 0.0, 0.0),
+        material,
+    );
+}
+
+/// Block spanning `size` from its corner, turned `degrees` about the y axis
+/// around that corner and moved to `offset`.
+fn block(size: Vec3, degrees: f32, offset: Point3, material: Box<dyn Material>) -> Instance {
+    let cuboid = Cuboid::new(Point3::new(0.0, 0.0, 0.0), size, material);
+    let transform = Transform::translate(offset) * Transform::rotate_y(degrees.to_radians());
+    Instance::new(Arc::new(cuboid), transform)
+}
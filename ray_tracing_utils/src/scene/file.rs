@@ -0,0 +1,557 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use toml::Value;
+
+use crate::math::{Vec3, Point3, Color};
+use crate::hittable::{Hittable, HittableList, Sphere, MovingSphere, Triangle, Plane, Quad, Cuboid};
+use crate::bvh::BvhNode;
+use crate::camera::Camera;
+use crate::material::{Material, Lambertian, Metal, Dielectric, DiffuseLight};
+use crate::medium::GridMedium;
+use crate::voxel;
+use crate::texture::{
+    Texture, SolidColor, CheckerTexture, ImageTexture, NoiseTexture, TurbulenceTexture, MarbleTexture, WoodTexture,
+};
+use crate::environment::{Environment, UniformEnvironment, GradientEnvironment, HdrEnvironment};
+use crate::render::RenderSettings;
+use crate::mesh::{obj, ply, stl, TriangleMesh};
+use crate::scene::Scene;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    /// The text is not valid TOML or JSON.
+    Syntax(String),
+    /// A missing, mistyped or unknown key, or an asset it names that could
+    /// not be loaded. `key` is a path such as `objects[2].radius`.
+    Invalid { key: String, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "I/O error: {}", e),
+            SceneError::Syntax(message) => write!(f, "syntax error: {}", message),
+            SceneError::Invalid { key, message } => write!(f, "{}: {}", key, message),
+        }
+    }
+}
+
+impl Error for SceneError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SceneError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(e: io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+/// Loads a scene file, read as JSON if it ends in `.json` and as TOML
+/// otherwise. Meshes and images are looked up relative to the file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json { from_json(&text, dir) } else { from_toml(&text, dir) }
+}
+
+pub fn from_toml(text: &str, dir: &Path) -> Result<Scene, SceneError> {
+    let root: Value = toml::from_str(text).map_err(|e| SceneError::Syntax(e.to_string()))?;
+    build(&root, dir)
+}
+
+/// Same layout as the TOML format, with JSON objects for tables.
+pub fn from_json(text: &str, dir: &Path) -> Result<Scene, SceneError> {
+    let root: Value = serde_json::from_str(text).map_err(|e| SceneError::Syntax(e.to_string()))?;
+    build(&root, dir)
+}
+
+/// A value together with the path that leads to it, for error messages.
+struct Node<'a> {
+    value: &'a Value,
+    path: String,
+}
+
+impl<'a> Node<'a> {
+    fn error(&self, message: impl Into<String>) -> SceneError {
+        SceneError::Invalid { key: self.path.clone(), message: message.into() }
+    }
+
+    fn expected(&self, what: &str) -> SceneError {
+        self.error(format!("expected {}, found {}", what, self.value.type_str()))
+    }
+
+    /// The value as a table whose keys must all be in `allowed`.
+    fn table(&self, allowed: &[&str]) -> Result<Table<'a>, SceneError> {
+        let table = self.any_table()?;
+        if let Some(key) = table.map.keys().find(|key| !allowed.contains(&key.as_str())) {
+            return Err(SceneError::Invalid {
+                key: table.key_path(key),
+                message: format!("unknown key, expected one of {}", allowed.join(", ")),
+            });
+        }
+        Ok(table)
+    }
+
+    fn any_table(&self) -> Result<Table<'a>, SceneError> {
+        let map = self.value.as_table().ok_or_else(|| self.expected("a table"))?;
+        Ok(Table { map, path: self.path.clone() })
+    }
+
+    fn f32(&self) -> Result<f32, SceneError> {
+        match self.value {
+            Value::Float(f) => Ok(*f as f32),
+            Value::Integer(i) => Ok(*i as f32),
+            _ => Err(self.expected("a number")),
+        }
+    }
+
+    fn positive(&self) -> Result<f32, SceneError> {
+        let value = self.f32()?;
+        if value > 0.0 { Ok(value) } else { Err(self.error("must be greater than 0")) }
+    }
+
+    fn nonzero(&self) -> Result<f32, SceneError> {
+        let value = self.f32()?;
+        if value != 0.0 { Ok(value) } else { Err(self.error("must not be 0")) }
+    }
+
+    fn u32(&self) -> Result<u32, SceneError> {
+        match self.value {
+            Value::Integer(i) => u32::try_from(*i).map_err(|_| self.error(format!("{} is out of range", i))),
+            _ => Err(self.expected("an integer")),
+        }
+    }
+
+    fn positive_u32(&self) -> Result<u32, SceneError> {
+        let value = self.u32()?;
+        if value > 0 { Ok(value) } else { Err(self.error("must be greater than 0")) }
+    }
+
+    fn str(&self) -> Result<&'a str, SceneError> {
+        self.value.as_str().ok_or_else(|| self.expected("a string"))
+    }
+
+    fn array(&self) -> Result<Vec<Node<'a>>, SceneError> {
+        let items = self.value.as_array().ok_or_else(|| self.expected("an array"))?;
+        Ok(items.iter().enumerate().map(|(i, value)| Node { value, path: format!("{}[{}]", self.path, i) }).collect())
+    }
+
+    fn vec3(&self) -> Result<Vec3, SceneError> {
+        let items = self.array()?;
+        if items.len() != 3 {
+            return Err(self.error(format!("expected 3 numbers, found {}", items.len())));
+        }
+        Ok(Vec3::new(items[0].f32()?, items[1].f32()?, items[2].f32()?))
+    }
+}
+
+struct Table<'a> {
+    map: &'a toml::Table,
+    path: String,
+}
+
+impl<'a> Table<'a> {
+    fn key_path(&self, key: &str) -> String {
+        if self.path.is_empty() { key.to_string() } else { format!("{}.{}", self.path, key) }
+    }
+
+    fn get(&self, key: &str) -> Option<Node<'a>> {
+        self.map.get(key).map(|value| Node { value, path: self.key_path(key) })
+    }
+
+    fn required(&self, key: &str) -> Result<Node<'a>, SceneError> {
+        self.get(key).ok_or_else(|| SceneError::Invalid { key: self.key_path(key), message: "missing required key".to_string() })
+    }
+
+    fn kind(&self) -> Result<(Node<'a>, &'a str), SceneError> {
+        let node = self.required("type")?;
+        let kind = node.str()?;
+        Ok((node, kind))
+    }
+}
+
+fn unknown_type(node: Node, kind: &str, expected: &str) -> SceneError {
+    node.error(format!("unknown type {:?}, expected one of {}", kind, expected))
+}
+
+fn build(root: &Value, dir: &Path) -> Result<Scene, SceneError> {
+    let root = Node { value: root, path: String::new() };
+    let root = root.table(&["camera", "render", "materials", "objects", "lights"])?;
+
+    let mut settings = RenderSettings::default();
+    if let Some(render) = root.get("render") {
+        parse_render(render, dir, &mut settings)?;
+    }
+    let camera = parse_camera(root.required("camera")?, settings.aspect_ratio())?;
+
+    let mut materials: HashMap<&str, Box<dyn Material>> = HashMap::new();
+    if let Some(node) = root.get("materials") {
+        // Any key names a material.
+        let table = node.any_table()?;
+        for name in table.map.keys() {
+            materials.insert(name, parse_material(table.get(name).unwrap(), dir)?);
+        }
+    }
+
+    let mut world = HittableList::default();
+    if let Some(objects) = root.get("objects") {
+        for object in objects.array()? {
+            world.hittables.push(parse_object(object, dir, &materials, camera.shutter())?);
+        }
+    }
+    if let Some(lights) = root.get("lights") {
+        for light in lights.array()? {
+            world.hittables.push(parse_light(light)?);
+        }
+    }
+
+    Ok(Scene { world: Box::new(BvhNode::new(world)), camera, settings })
+}
+
+fn parse_render(node: Node, dir: &Path, settings: &mut RenderSettings) -> Result<(), SceneError> {
+    let table = node.table(&["width", "height", "samples_per_pixel", "max_depth", "environment"])?;
+    if let Some(width) = table.get("width") {
+        settings.width = width.positive_u32()?;
+    }
+    if let Some(height) = table.get("height") {
+        settings.height = height.positive_u32()?;
+    }
+    if let Some(spp) = table.get("samples_per_pixel") {
+        settings.samples_per_pixel = spp.positive_u32()?;
+    }
+    if let Some(depth) = table.get("max_depth") {
+        settings.max_depth = depth.u32()?;
+    }
+    if let Some(environment) = table.get("environment") {
+        settings.environment = parse_environment(environment, dir)?;
+    }
+    Ok(())
+}
+
+/// The `Camera::new` parameters, with `vfov` in degrees. The aspect ratio
+/// comes from the render size.
+fn parse_camera(node: Node, aspect_ratio: f32) -> Result<Camera, SceneError> {
+    let table = node.table(&["lookfrom", "lookat", "vup", "vfov", "aperture", "focus_dist", "shutter"])?;
+    let lookfrom = table.required("lookfrom")?.vec3()?;
+    let lookat = table.required("lookat")?.vec3()?;
+    if lookfrom == lookat {
+        return Err(table.required("lookat")?.error("must differ from lookfrom"));
+    }
+    let vup = table.get("vup").map_or(Ok(Vec3::new(0.0, 1.0, 0.0)), |n| n.vec3())?;
+    if Vec3::cross(lookfrom - lookat, vup).length_squared() == 0.0 {
+        let node = match table.get("vup") {
+            Some(n) => n,
+            None => table.required("lookat")?,
+        };
+        return Err(node.error("vup must not be parallel to lookfrom - lookat"));
+    }
+    let vfov = table.required("vfov")?;
+    let degrees = vfov.f32()?;
+    if !(degrees > 0.0 && degrees < 180.0) {
+        return Err(vfov.error("must be between 0 and 180 degrees"));
+    }
+    let aperture = table.get("aperture").map_or(Ok(0.0), |n| n.f32())?;
+    let focus_dist = match table.get("focus_dist") {
+        Some(n) => n.positive()?,
+        None => (lookfrom - lookat).length(),
+    };
+    let camera = Camera::new(lookfrom, lookat, vup, degrees.to_radians(), aspect_ratio, aperture, focus_dist);
+    let Some(shutter) = table.get("shutter") else { return Ok(camera) };
+    let times = shutter.array()?;
+    if times.len() != 2 {
+        return Err(shutter.error(format!("expected [open, close], found {} numbers", times.len())));
+    }
+    let (open, close) = (times[0].f32()?, times[1].f32()?);
+    if open > close {
+        return Err(shutter.error("must not close before it opens"));
+    }
+    Ok(camera.with_shutter(open, close))
+}
+
+fn parse_environment(node: Node, dir: &Path) -> Result<Arc<dyn Environment>, SceneError> {
+    let (type_node, kind) = node.any_table()?.kind()?;
+    Ok(match kind {
+        "sky" => {
+            node.table(&["type"])?;
+            Arc::new(GradientEnvironment::sky())
+        },
+        "uniform" => {
+            let table = node.table(&["type", "color"])?;
+            Arc::new(UniformEnvironment { color: table.required("color")?.vec3()? })
+        },
+        "gradient" => {
+            let table = node.table(&["type", "bottom", "top"])?;
+            Arc::new(GradientEnvironment {
+                bottom: table.required("bottom")?.vec3()?,
+                top: table.required("top")?.vec3()?,
+            })
+        },
+        "hdr" => {
+            let table = node.table(&["type", "path", "intensity"])?;
+            let path = table.required("path")?;
+            let mut environment = HdrEnvironment::from_path(dir.join(path.str()?))
+                .map_err(|e| path.error(format!("cannot load {:?}: {}", path.str().unwrap_or(""), e)))?;
+            if let Some(intensity) = table.get("intensity") {
+                environment.set_intensity(intensity.f32()?);
+            }
+            Arc::new(environment)
+        },
+        _ => return Err(unknown_type(type_node, kind, "sky, uniform, gradient, hdr")),
+    })
+}
+
+fn parse_texture(node: Node, dir: &Path) -> Result<Arc<dyn Texture>, SceneError> {
+    let (type_node, kind) = node.any_table()?.kind()?;
+    // The procedural textures other than wood share their keys.
+    let noise = || -> Result<(u64, f32), SceneError> {
+        let table = node.table(&["type", "seed", "scale"])?;
+        let seed = table.get("seed").map_or(Ok(0), |n| n.u32())?;
+        Ok((seed as u64, table.get("scale").map_or(Ok(1.0), |n| n.positive())?))
+    };
+    Ok(match kind {
+        "solid" => {
+            let table = node.table(&["type", "color"])?;
+            Arc::new(SolidColor::new(table.required("color")?.vec3()?))
+        },
+        "checker" => {
+            let table = node.table(&["type", "even", "odd", "size"])?;
+            Arc::new(CheckerTexture::new(
+                table.required("even")?.vec3()?,
+                table.required("odd")?.vec3()?,
+                table.get("size").map_or(Ok(1.0), |n| n.positive())?,
+            ))
+        },
+        "image" => {
+            let table = node.table(&["type", "path"])?;
+            let path = table.required("path")?;
+            let texture = ImageTexture::from_path(dir.join(path.str()?))
+                .map_err(|e| path.error(format!("cannot load {:?}: {}", path.str().unwrap_or(""), e)))?;
+            Arc::new(texture)
+        },
+        "noise" => {
+            let (seed, scale) = noise()?;
+            Arc::new(NoiseTexture::new(seed, scale))
+        },
+        "turbulence" => {
+            let (seed, scale) = noise()?;
+            Arc::new(TurbulenceTexture::new(seed, scale))
+        },
+        "marble" => {
+            let (seed, scale) = noise()?;
+            Arc::new(MarbleTexture::new(seed, scale))
+        },
+        "wood" => {
+            let table = node.table(&["type", "seed", "rings"])?;
+            Arc::new(WoodTexture::new(
+                table.get("seed").map_or(Ok(0), |n| n.u32())? as u64,
+                table.get("rings").map_or(Ok(4.0), |n| n.positive())?,
+            ))
+        },
+        _ => return Err(unknown_type(type_node, kind, "solid, checker, image, noise, turbulence, marble, wood")),
+    })
+}
+
+/// `albedo` as a color, or `texture` as a texture table.
+fn parse_albedo(table: &Table, dir: &Path) -> Result<Arc<dyn Texture>, SceneError> {
+    match (table.get("albedo"), table.get("texture")) {
+        (Some(albedo), None) => Ok(Arc::new(SolidColor::new(albedo.vec3()?))),
+        (None, Some(texture)) => parse_texture(texture, dir),
+        (Some(_), Some(texture)) => Err(texture.error("give either albedo or texture, not both")),
+        (None, None) => Err(table.required("albedo").err().unwrap()),
+    }
+}
+
+fn parse_material(node: Node, dir: &Path) -> Result<Box<dyn Material>, SceneError> {
+    let (type_node, kind) = node.any_table()?.kind()?;
+    Ok(match kind {
+        "lambertian" => {
+            let table = node.table(&["type", "albedo", "texture"])?;
+            Box::new(Lambertian::textured(parse_albedo(&table, dir)?))
+        },
+        "metal" => {
+            let table = node.table(&["type", "albedo", "texture", "fuzz"])?;
+            let fuzz = table.get("fuzz").map_or(Ok(0.0), |n| n.f32())?;
+            Box::new(Metal::textured(parse_albedo(&table, dir)?, fuzz))
+        },
+        "dielectric" => {
+            let table = node.table(&["type", "ior"])?;
+            Box::new(Dielectric { ref_idx: table.required("ior")?.positive()? })
+        },
+        "diffuse_light" => {
+            let table = node.table(&["type", "emit"])?;
+            Box::new(DiffuseLight { emit: table.required("emit")?.vec3()? })
+        },
+        _ => return Err(unknown_type(type_node, kind, "lambertian, metal, dielectric, diffuse_light")),
+    })
+}
+
+fn material_ref(node: Node, materials: &HashMap<&str, Box<dyn Material>>) -> Result<Box<dyn Material>, SceneError> {
+    let name = node.str()?;
+    materials.get(name)
+        .map(|material| dyn_clone::clone_box(&**material))
+        .ok_or_else(|| node.error(format!("no material named {:?}", name)))
+}
+
+fn parse_vertices(node: Node) -> Result<[Point3; 3], SceneError> {
+    let items = node.array()?;
+    if items.len() != 3 {
+        return Err(node.error(format!("expected 3 vertices, found {}", items.len())));
+    }
+    Ok([items[0].vec3()?, items[1].vec3()?, items[2].vec3()?])
+}
+
+fn parse_object(
+    node: Node,
+    dir: &Path,
+    materials: &HashMap<&str, Box<dyn Material>>,
+    (open, close): (f32, f32),
+) -> Result<Box<dyn Hittable>, SceneError> {
+    // Each type only takes its own keys, so one meant for another is reported.
+    let (type_node, kind) = node.any_table()?.kind()?;
+    Ok(match kind {
+        "sphere" => {
+            let table = node.table(&["type", "center", "center1", "time0", "time1", "radius", "material"])?;
+            let center = table.required("center")?.vec3()?;
+            // A negative radius turns the normals inward, for hollow glass.
+            let radius = table.required("radius")?.nonzero()?;
+            let material = material_ref(table.required("material")?, materials)?;
+            // With `center1` the sphere moves there from `center` between
+            // `time0` and `time1`, by default while the shutter is open.
+            let Some(center1) = table.get("center1") else {
+                return Ok(Box::new(Sphere { center, radius, material }));
+            };
+            let time0 = table.get("time0").map_or(Ok(open), |n| n.f32())?;
+            let time1 = match table.get("time1") {
+                Some(n) if n.f32()? < time0 => return Err(n.error("must not be before time0")),
+                Some(n) => n.f32()?,
+                None => close,
+            };
+            Box::new(MovingSphere { center0: center, center1: center1.vec3()?, time0, time1, radius, material })
+        },
+        "triangle" => {
+            let table = node.table(&["type", "vertices", "material"])?;
+            let [a, b, c] = parse_vertices(table.required("vertices")?)?;
+            Box::new(Triangle::new(a, b, c, material_ref(table.required("material")?, materials)?))
+        },
+        "plane" => {
+            let table = node.table(&["type", "point", "normal", "material"])?;
+            let point = table.required("point")?.vec3()?;
+            let normal_node = table.required("normal")?;
+            let normal = normal_node.vec3()?;
+            if normal.length_squared() == 0.0 {
+                return Err(normal_node.error("must not be zero"));
+            }
+            Box::new(Plane::new(point, normal, material_ref(table.required("material")?, materials)?))
+        },
+        "quad" => {
+            let table = node.table(&["type", "corner", "u", "v", "material"])?;
+            let (corner, u, v) = parse_quad(&table)?;
+            Box::new(Quad::new(corner, u, v, material_ref(table.required("material")?, materials)?))
+        },
+        "box" => {
+            let table = node.table(&["type", "min", "max", "material"])?;
+            let min = table.required("min")?.vec3()?;
+            let max = table.required("max")?.vec3()?;
+            Box::new(Cuboid::new(min, max, material_ref(table.required("material")?, materials)?))
+        },
+        "mesh" => {
+            let table = node.table(&["type", "path", "material"])?;
+            let path = table.required("path")?;
+            let name = path.str()?;
+            let file = dir.join(name);
+            let extension = file.extension().and_then(|ext| ext.to_str()).unwrap_or("").to_ascii_lowercase();
+            let mesh = match extension.as_str() {
+                "obj" => obj::load(&file).map_err(|e| e.to_string()),
+                "ply" => ply::load(&file).map_err(|e| e.to_string()),
+                "stl" => stl::load(&file).map_err(|e| e.to_string()),
+                _ => return Err(path.error("unsupported mesh format, expected .obj, .ply or .stl")),
+            };
+            let mesh: TriangleMesh = mesh.map_err(|e| path.error(format!("cannot load {:?}: {}", name, e)))?;
+            // Without a material the mesh keeps its own.
+            match table.get("material") {
+                Some(material) => Box::new(mesh.with_material(material_ref(material, materials)?)),
+                None => Box::new(mesh),
+            }
+        },
+        "volume" => {
+            let table = node.table(&["type", "path", "density", "albedo", "emit", "anisotropy"])?;
+            let path = table.required("path")?;
+            let name = path.str()?;
+            let grid = voxel::load(dir.join(name))
+                .map_err(|e| path.error(format!("cannot load {:?}: {}", name, e)))?;
+            let density = table.required("density")?.positive()?;
+            let albedo = match table.get("albedo") {
+                Some(albedo) => albedo.vec3()?,
+                None => Color::new(1.0, 1.0, 1.0),
+            };
+            let mut medium = GridMedium::new(Arc::new(grid), density, albedo);
+            if let Some(emit) = table.get("emit") {
+                medium = medium.with_emission(emit.vec3()?);
+            }
+            if let Some(node) = table.get("anisotropy") {
+                let g = node.f32()?;
+                if !(-1.0 < g && g < 1.0) {
+                    return Err(node.error("must be between -1 and 1"));
+                }
+                medium = medium.with_anisotropy(g);
+            }
+            Box::new(medium)
+        },
+        _ => return Err(unknown_type(type_node, kind, "sphere, triangle, plane, quad, box, mesh, volume")),
+    })
+}
+
+/// Lights are objects with an `emit` color instead of a material.
+fn parse_light(node: Node) -> Result<Box<dyn Hittable>, SceneError> {
+    let (type_node, kind) = node.any_table()?.kind()?;
+    let material = |table: &Table| -> Result<Box<dyn Material>, SceneError> {
+        Ok(Box::new(DiffuseLight { emit: table.required("emit")?.vec3()? }))
+    };
+    Ok(match kind {
+        "sphere" => {
+            let table = node.table(&["type", "center", "radius", "emit"])?;
+            Box::new(Sphere {
+                center: table.required("center")?.vec3()?,
+                radius: table.required("radius")?.positive()?,
+                material: material(&table)?,
+            })
+        },
+        "triangle" => {
+            let table = node.table(&["type", "vertices", "emit"])?;
+            let [a, b, c] = parse_vertices(table.required("vertices")?)?;
+            Box::new(Triangle::new(a, b, c, material(&table)?))
+        },
+        "quad" => {
+            let table = node.table(&["type", "corner", "u", "v", "emit"])?;
+            let (corner, u, v) = parse_quad(&table)?;
+            Box::new(Quad::new(corner, u, v, material(&table)?))
+        },
+        _ => return Err(unknown_type(type_node, kind, "sphere, triangle, quad")),
+    })
+}
+
+/// A corner and the two edges leaving it.
+fn parse_quad(table: &Table) -> Result<(Point3, Vec3, Vec3), SceneError> {
+    let corner = table.required("corner")?.vec3()?;
+    let u = table.required("u")?.vec3()?;
+    let v_node = table.required("v")?;
+    let v = v_node.vec3()?;
+    if Vec3::cross(u, v).length_squared() == 0.0 {
+        return Err(v_node.error("must not be parallel to u"));
+    }
+    Ok((corner, u, v))
+}
@@ -0,0 +1,386 @@
+//! glTF 2.0 scenes, converted into the renderer's own, simpler model:
+//!
+//! - Materials with an emissive factor become lights and transmissive ones
+//!   glass. Of the rest, those with a metallic factor of at least 0.5 become
+//!   metal with the roughness as fuzz, and everything else is diffuse; there
+//!   is no blend in between.
+//! - Only the base color is textured, read through whichever texture
+//!   coordinate set it names. Other texture maps are ignored.
+//! - Point and spot lights become small emissive spheres, and directional
+//!   lights distant disks; spot cones are not modeled.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use ::gltf::khr_lights_punctual::Kind;
+use ::gltf::mesh::Mode;
+use ::gltf::camera::Projection;
+
+use crate::math::{Vec3, Point3, Color, Transform};
+use crate::aabb::Aabb;
+use crate::hittable::{Hittable, HittableList, Sphere};
+use crate::bvh::BvhNode;
+use crate::camera::Camera;
+use crate::material::{Material, Lambertian, Metal, Dielectric, DiffuseLight};
+use crate::texture::{Texture, ImageTexture, TintedTexture};
+use crate::image::Pixel;
+use crate::environment::UniformEnvironment;
+use crate::render::RenderSettings;
+use crate::mesh::{TriangleMesh, MeshTriangle};
+use crate::scene::Scene;
+
+#[derive(Debug)]
+pub enum GltfError {
+    /// The file could not be read, parsed or validated, or a buffer or
+    /// image it refers to could not be loaded.
+    Gltf(::gltf::Error),
+    /// The file has no scene to render.
+    NoScene,
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GltfError::Gltf(e) => write!(f, "glTF error: {}", e),
+            GltfError::NoScene => write!(f, "the file has no scene"),
+        }
+    }
+}
+
+impl Error for GltfError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GltfError::Gltf(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<::gltf::Error> for GltfError {
+    fn from(e: ::gltf::Error) -> Self {
+        GltfError::Gltf(e)
+    }
+}
+
+/// How glTF quantities without a direct counterpart are brought into the
+/// renderer.
+#[derive(Debug, Clone)]
+pub struct GltfOptions {
+    /// Multiplies every light's intensity. Exporters write physical units
+    /// (candela, lux), which are far brighter than the renderer's unit
+    /// radiance.
+    pub light_scale: f32,
+    /// Radius of the emissive spheres standing in for point and spot lights.
+    pub light_radius: f32,
+}
+
+impl Default for GltfOptions {
+    fn default() -> Self {
+        GltfOptions { light_scale: 1.0, light_radius: 0.05 }
+    }
+}
+
+/// Loads a `.gltf` or `.glb` file with default options.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, GltfError> {
+    load_with_options(path, &GltfOptions::default())
+}
+
+pub fn load_with_options<P: AsRef<Path>>(path: P, options: &GltfOptions) -> Result<Scene, GltfError> {
+    let (document, buffers, images) = ::gltf::import(path)?;
+    convert(&document, &buffers, &images, options)
+}
+
+/// Loads a `.glb` file or self-contained `.gltf` (with data URIs) from memory.
+pub fn from_slice(data: &[u8], options: &GltfOptions) -> Result<Scene, GltfError> {
+    let (document, buffers, images) = ::gltf::import_slice(data)?;
+    convert(&document, &buffers, &images, options)
+}
+
+/// Collects the scene while walking the node hierarchy.
+struct Builder<'a> {
+    buffers: &'a [::gltf::buffer::Data],
+    images: &'a [::gltf::image::Data],
+    options: &'a GltfOptions,
+
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f32, f32)>,
+    triangles: Vec<MeshTriangle>,
+    materials: Vec<Box<dyn Material>>,
+    // glTF material index (`None` for the default material) to mesh slot.
+    material_slots: HashMap<Option<usize>, u32>,
+    textures: HashMap<usize, Option<Arc<dyn Texture>>>,
+
+    lights: Vec<Box<dyn Hittable>>,
+    // Directional lights, placed once the size of the scene is known.
+    suns: Vec<(Vec3, Color)>,
+    // Transform, vertical field of view and aspect ratio of the first
+    // perspective camera.
+    camera: Option<(Transform, f32, Option<f32>)>,
+    has_emissive_materials: bool,
+}
+
+impl<'a> Builder<'a> {
+    fn visit(&mut self, node: ::gltf::Node<'a>, parent: &Transform) {
+        // A zero scale hides the node and everything below it.
+        let Some(local) = Transform::from_columns(node.transform().matrix()) else { return };
+        let transform = *parent * local;
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                self.add_primitive(&primitive, &transform);
+            }
+        }
+        if let Some(camera) = node.camera() {
+            if let (None, Projection::Perspective(perspective)) = (&self.camera, camera.projection()) {
+                self.camera = Some((transform, perspective.yfov(), perspective.aspect_ratio()));
+            }
+        }
+        if let Some(light) = node.light() {
+            self.add_light(&light, &transform);
+        }
+
+        for child in node.children() {
+            self.visit(child, &transform);
+        }
+    }
+
+    fn add_primitive(&mut self, primitive: &::gltf::Primitive<'a>, transform: &Transform) {
+        let buffers = self.buffers;
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+        let Some(positions) = reader.read_positions() else { return };
+
+        let base = self.positions.len() as u32;
+        self.positions.extend(positions.map(|p| transform.transform_point(Vec3::new(p[0], p[1], p[2]))));
+        let count = self.positions.len() as u32 - base;
+
+        let normals: Vec<Vec3> = reader.read_normals()
+            .map(|n| n.map(|n| transform.transform_normal(Vec3::new(n[0], n[1], n[2])).normalized()).collect())
+            .unwrap_or_default();
+        let has_normals = normals.len() == count as usize;
+        let normal_base = self.normals.len() as u32;
+        if has_normals {
+            self.normals.extend(normals);
+        }
+
+        // Only the set the base color texture samples is needed. glTF puts
+        // v = 0 at the top of the image.
+        let set = primitive.material().pbr_metallic_roughness().base_color_texture().map_or(0, |info| info.tex_coord());
+        let uvs: Vec<(f32, f32)> = reader.read_tex_coords(set)
+            .map(|uv| uv.into_f32().map(|[u, v]| (u, 1.0 - v)).collect())
+            .unwrap_or_default();
+        let has_uvs = uvs.len() == count as usize;
+        let uv_base = self.uvs.len() as u32;
+        if has_uvs {
+            self.uvs.extend(uvs);
+        }
+
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..count).collect(),
+        };
+        let corners: Vec<[u32; 3]> = match primitive.mode() {
+            Mode::Triangles => indices.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            Mode::TriangleStrip => (2..indices.len()).map(|i| {
+                // Every other triangle of a strip is wound the other way.
+                if i % 2 == 0 {
+                    [indices[i - 2], indices[i - 1], indices[i]]
+                } else {
+                    [indices[i - 1], indices[i - 2], indices[i]]
+                }
+            }).collect(),
+            Mode::TriangleFan => (2..indices.len()).map(|i| [indices[0], indices[i - 1], indices[i]]).collect(),
+            // Points and lines have no area to hit.
+            _ => return,
+        };
+
+        let material = self.material_slot(&primitive.material());
+        // A mirroring transform turns the winding, and with it the front face, around.
+        let mirrored = transform.determinant() < 0.0;
+        for mut tri in corners {
+            if tri.iter().any(|&i| i >= count) {
+                continue;
+            }
+            if mirrored {
+                tri.swap(1, 2);
+            }
+            self.triangles.push(MeshTriangle {
+                positions: tri.map(|i| base + i),
+                normals: has_normals.then(|| tri.map(|i| normal_base + i)),
+                uvs: has_uvs.then(|| tri.map(|i| uv_base + i)),
+                material,
+            });
+        }
+    }
+
+    fn material_slot(&mut self, material: &::gltf::Material<'a>) -> u32 {
+        if let Some(&slot) = self.material_slots.get(&material.index()) {
+            return slot;
+        }
+        let converted = self.convert_material(material);
+        self.materials.push(converted);
+        let slot = self.materials.len() as u32 - 1;
+        self.material_slots.insert(material.index(), slot);
+        slot
+    }
+
+    /// Emissive materials become lights, transmissive ones glass, mostly
+    /// metallic ones metal with the roughness as fuzz, and the rest diffuse.
+    fn convert_material(&mut self, material: &::gltf::Material<'a>) -> Box<dyn Material> {
+        let [r, g, b] = material.emissive_factor();
+        let emit = Color::new(r, g, b) * material.emissive_strength().unwrap_or(1.0);
+        if emit.x > 0.0 || emit.y > 0.0 || emit.z > 0.0 {
+            self.has_emissive_materials = true;
+            return Box::new(DiffuseLight { emit });
+        }
+        if material.transmission().is_some_and(|t| t.transmission_factor() > 0.0) {
+            return Box::new(Dielectric { ref_idx: material.ior().unwrap_or(1.5) });
+        }
+
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor();
+        let base_color = Color::new(r, g, b);
+        let mut texture = pbr.base_color_texture().and_then(|info| self.texture(info.texture().source().index()));
+        // The factor scales the texture rather than standing in for it.
+        if base_color != Color::new(1.0, 1.0, 1.0) {
+            texture = texture.map(|texture| Arc::new(TintedTexture { texture, tint: base_color }) as Arc<dyn Texture>);
+        }
+        let metallic = pbr.metallic_factor() >= 0.5;
+
+        match (texture, metallic) {
+            (Some(texture), true) => Box::new(Metal::textured(texture, pbr.roughness_factor())),
+            (None, true) => Box::new(Metal::new(base_color, pbr.roughness_factor())),
+            (Some(texture), false) => Box::new(Lambertian::textured(texture)),
+            (None, false) => Box::new(Lambertian::new(base_color)),
+        }
+    }
+
+    /// 8-bit RGB(A) images, decoded like the crate's other 8-bit images;
+    /// other pixel formats fall back to the base color.
+    fn texture(&mut self, image: usize) -> Option<Arc<dyn Texture>> {
+        let images = self.images;
+        self.textures.entry(image).or_insert_with(|| {
+            let data = images.get(image)?;
+            let channels = match data.format {
+                ::gltf::image::Format::R8G8B8 => 3,
+                ::gltf::image::Format::R8G8B8A8 => 4,
+                _ => return None,
+            };
+            let texels = data.pixels.chunks_exact(channels)
+                .map(|px| Pixel { r: px[0], g: px[1], b: px[2], a: 255 }.to_linear())
+                .collect();
+            Some(Arc::new(ImageTexture::new(data.width, data.height, texels)) as Arc<dyn Texture>)
+        }).clone()
+    }
+
+    /// Point and spot lights become small emissive spheres with the same
+    /// intensity; spot cones are not modeled.
+    fn add_light(&mut self, light: &::gltf::khr_lights_punctual::Light<'a>, transform: &Transform) {
+        let [r, g, b] = light.color();
+        let intensity = Color::new(r, g, b) * light.intensity() * self.options.light_scale;
+        match light.kind() {
+            Kind::Directional => {
+                // Lights shine down their local -z axis.
+                let direction = transform.transform_vector(Vec3::new(0.0, 0.0, -1.0)).normalized();
+                self.suns.push((direction, intensity));
+            },
+            Kind::Point | Kind::Spot { .. } => {
+                let radius = self.options.light_radius;
+                // A sphere of radiance L has intensity L * pi * r^2 in every direction.
+                let emit = intensity / (PI * radius * radius);
+                self.lights.push(Box::new(Sphere {
+                    center: transform.transform_point(Point3::default()),
+                    radius,
+                    material: Box::new(DiffuseLight { emit }),
+                }));
+            },
+        }
+    }
+}
+
+// Angular radius of the disk standing in for a directional light. Smaller
+// is sharper but, as the disk is only found by scattered rays, noisier.
+const SUN_ANGULAR_RADIUS: f32 = 0.05;
+
+fn convert(
+    document: &::gltf::Document,
+    buffers: &[::gltf::buffer::Data],
+    images: &[::gltf::image::Data],
+    options: &GltfOptions,
+) -> Result<Scene, GltfError> {
+    let scene = document.default_scene().or_else(|| document.scenes().next()).ok_or(GltfError::NoScene)?;
+
+    let mut builder = Builder {
+        buffers, images, options,
+        positions: vec![], normals: vec![], uvs: vec![], triangles: vec![], materials: vec![],
+        material_slots: HashMap::new(),
+        textures: HashMap::new(),
+        lights: vec![],
+        suns: vec![],
+        camera: None,
+        has_emissive_materials: false,
+    };
+    for node in scene.nodes() {
+        builder.visit(node, &Transform::identity());
+    }
+
+    let bounds = builder.positions.iter().fold(Aabb::empty(), |b, &p| b.grow(p));
+    let (center, radius) = if bounds.is_empty() {
+        (Point3::default(), 1.0)
+    } else {
+        (bounds.centroid(), (bounds.extent().length() / 2.0).max(1e-3))
+    };
+
+    let has_lights = !builder.lights.is_empty() || !builder.suns.is_empty() || builder.has_emissive_materials;
+
+    let mut world = HittableList::default();
+    for (direction, irradiance) in builder.suns {
+        // Far enough away to look like a disk at infinity from the whole scene.
+        let distance = 1000.0 * radius;
+        let sun_radius = distance * SUN_ANGULAR_RADIUS.tan();
+        let emit = irradiance / (PI * SUN_ANGULAR_RADIUS.sin().powi(2));
+        world.hittables.push(Box::new(Sphere {
+            center: center - direction * distance,
+            radius: sun_radius,
+            material: Box::new(DiffuseLight { emit }),
+        }));
+    }
+    world.hittables.append(&mut builder.lights);
+    if !builder.triangles.is_empty() {
+        world.hittables.push(Box::new(TriangleMesh::new(
+            builder.positions, builder.normals, builder.uvs, builder.triangles, builder.materials,
+        )));
+    }
+
+    let mut settings = RenderSettings::default();
+    if has_lights {
+        // Lit scenes bring their own light; the default sky would wash them out.
+        settings.environment = Arc::new(UniformEnvironment::default());
+    }
+
+    let camera = match builder.camera {
+        Some((transform, yfov, aspect_ratio)) => {
+            if let Some(aspect_ratio) = aspect_ratio.filter(|&a| a > 0.0) {
+                settings.height = ((settings.width as f32 / aspect_ratio).round() as u32).max(1);
+            }
+            // Cameras look down their local -z axis with +y up.
+            let lookfrom = transform.transform_point(Point3::default());
+            let lookat = transform.transform_point(Point3::new(0.0, 0.0, -1.0));
+            let vup = transform.transform_vector(Vec3::new(0.0, 1.0, 0.0));
+            Camera::new(lookfrom, lookat, vup, yfov, settings.aspect_ratio(), 0.0, 1.0)
+        },
+        None => {
+            // Frame the whole scene from +z.
+            let vfov = 40.0f32.to_radians();
+            let distance = 1.1 * radius / (vfov / 2.0).sin();
+            let lookfrom = center + Vec3::new(0.0, 0.0, distance);
+            Camera::new(lookfrom, center, Vec3::new(0.0, 1.0, 0.0), vfov, settings.aspect_ratio(), 0.0, 1.0)
+        },
+    };
+
+    Ok(Scene { world: Box::new(BvhNode::new(world)), camera, settings })
+}
@@ -0,0 +1,15 @@
+pub mod gltf;
+pub mod file;
+pub mod builtin;
+
+use crate::hittable::Hittable;
+use crate::camera::Camera;
+use crate::render::RenderSettings;
+
+/// Everything needed to render an image: the world, the camera looking at
+/// it and the settings, whose aspect ratio matches the camera's.
+pub struct Scene {
+    pub world: Box<dyn Hittable>,
+    pub camera: Camera,
+    pub settings: RenderSettings,
+}
@@ -0,0 +1,264 @@
+use std::error::Error;
+use std::f32::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::math::{Color, Point3};
+use crate::image::Image;
+use crate::hdr::HdrImage;
+use crate::perlin::Perlin;
+use crate::hittable::HitRecord;
+
+/// Color varying over a surface, looked up by surface coordinates `u`, `v`
+/// and the hit point `p`.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f32, v: f32, p: Point3) -> Color;
+
+    /// Color at a hit. Materials look textures up through this, so a texture
+    /// can use more of the hit than its surface coordinates.
+    fn value_at(&self, rec: &HitRecord) -> Color {
+        self.value(rec.u, rec.v, rec.p)
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Self {
+        SolidColor { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f32, _v: f32, _p: Point3) -> Color {
+        self.color
+    }
+}
+
+/// Colors interpolated from a mesh's vertices, or `fallback` on surfaces
+/// without vertex colors.
+#[derive(Default, Clone)]
+pub struct VertexColorTexture {
+    pub fallback: Color,
+}
+
+impl Texture for VertexColorTexture {
+    fn value(&self, _u: f32, _v: f32, _p: Point3) -> Color {
+        self.fallback
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Color {
+        rec.color.unwrap_or(self.fallback)
+    }
+}
+
+/// Checkerboard of cubes with edge `size` in world space, alternating
+/// between two textures.
+#[derive(Clone)]
+pub struct CheckerTexture {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub size: f32,
+}
+
+impl CheckerTexture {
+    pub fn new(even: Color, odd: Color, size: f32) -> Self {
+        CheckerTexture {
+            even: Arc::new(SolidColor::new(even)),
+            odd: Arc::new(SolidColor::new(odd)),
+            size,
+        }
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f32, v: f32, p: Point3) -> Color {
+        let cell = |x: f32| (x / self.size).floor() as i64;
+        if (cell(p.x) + cell(p.y) + cell(p.z)).rem_euclid(2) == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+/// Another texture multiplied by a constant color.
+#[derive(Clone)]
+pub struct TintedTexture {
+    pub texture: Arc<dyn Texture>,
+    pub tint: Color,
+}
+
+impl Texture for TintedTexture {
+    fn value(&self, u: f32, v: f32, p: Point3) -> Color {
+        self.texture.value(u, v, p) * self.tint
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Color {
+        self.texture.value_at(rec) * self.tint
+    }
+}
+
+/// Image mapped onto `u`, `v` in `[0, 1]`, with `v = 0` at the bottom row.
+#[derive(Clone)]
+pub struct ImageTexture {
+    pub width: u32,
+    pub height: u32,
+    pub texels: Vec<Color>,
+}
+
+impl ImageTexture {
+    pub fn new(width: u32, height: u32, texels: Vec<Color>) -> Self {
+        assert_eq!(texels.len(), width as usize * height as usize);
+        ImageTexture { width, height, texels }
+    }
+
+    /// Uses an 8-bit image, undoing the gamma 2 encoding the renderer writes.
+    pub fn from_image(image: &Image) -> Self {
+        ImageTexture::new(image.width, image.height, image.to_linear())
+    }
+
+    pub fn from_hdr(image: HdrImage) -> Self {
+        ImageTexture::new(image.width, image.height, image.data)
+    }
+
+    /// Loads a Radiance `.hdr` or `.png` file, or a PNM file for any other
+    /// extension.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("").to_ascii_lowercase();
+        Ok(match extension.as_str() {
+            "hdr" => ImageTexture::from_hdr(HdrImage::from_path(path)?),
+            "png" => ImageTexture::from_image(&Image::from_png_path(path)?),
+            _ => ImageTexture::from_image(&Image::from_path(path)?),
+        })
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _p: Point3) -> Color {
+        if self.texels.is_empty() {
+            // Cyan stands out as a missing texture.
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        let u = u.clamp(0.0, 1.0);
+        let v = 1.0 - v.clamp(0.0, 1.0);
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        self.texels[y as usize * self.width as usize + x as usize]
+    }
+}
+
+/// Smooth Perlin noise mapped to `[0, 1]` and tinted by `color`. Larger
+/// `scale` gives finer features.
+#[derive(Clone)]
+pub struct NoiseTexture {
+    pub noise: Perlin,
+    pub scale: f32,
+    pub color: Color,
+}
+
+impl NoiseTexture {
+    pub fn new(seed: u64, scale: f32) -> Self {
+        NoiseTexture { noise: Perlin::with_seed(seed), scale, color: Color::new(1.0, 1.0, 1.0) }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        self.color * (0.5 * (1.0 + self.noise.noise(p * self.scale)))
+    }
+}
+
+/// Turbulence (summed absolute noise), giving a cloudy, veined look.
+#[derive(Clone)]
+pub struct TurbulenceTexture {
+    pub noise: Perlin,
+    pub scale: f32,
+    pub octaves: u32,
+    pub color: Color,
+}
+
+impl TurbulenceTexture {
+    pub fn new(seed: u64, scale: f32) -> Self {
+        TurbulenceTexture {
+            noise: Perlin::with_seed(seed),
+            scale,
+            octaves: 7,
+            color: Color::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Texture for TurbulenceTexture {
+    fn value(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        self.color * self.noise.turbulence(p * self.scale, self.octaves).min(1.0)
+    }
+}
+
+/// Marble: sine stripes along z whose phase is disturbed by turbulence.
+#[derive(Clone)]
+pub struct MarbleTexture {
+    pub noise: Perlin,
+    pub scale: f32,
+    pub octaves: u32,
+    /// How far turbulence bends the stripes.
+    pub distortion: f32,
+    pub color: Color,
+}
+
+impl MarbleTexture {
+    pub fn new(seed: u64, scale: f32) -> Self {
+        MarbleTexture {
+            noise: Perlin::with_seed(seed),
+            scale,
+            octaves: 7,
+            distortion: 10.0,
+            color: Color::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        let phase = self.scale * p.z + self.distortion * self.noise.turbulence(p, self.octaves);
+        self.color * (0.5 * (1.0 + phase.sin()))
+    }
+}
+
+/// Wood: concentric rings around the y axis, `rings` per unit of radius,
+/// wobbled by fractal noise and blended between `light` and `dark`.
+#[derive(Clone)]
+pub struct WoodTexture {
+    pub noise: Perlin,
+    pub rings: f32,
+    pub octaves: u32,
+    pub distortion: f32,
+    pub light: Color,
+    pub dark: Color,
+}
+
+impl WoodTexture {
+    pub fn new(seed: u64, rings: f32) -> Self {
+        WoodTexture {
+            noise: Perlin::with_seed(seed),
+            rings,
+            octaves: 4,
+            distortion: 0.5,
+            light: Color::new(0.75, 0.55, 0.33),
+            dark: Color::new(0.4, 0.24, 0.11),
+        }
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _u: f32, _v: f32, p: Point3) -> Color {
+        let radius = (p.x * p.x + p.z * p.z).sqrt();
+        let wobble = self.distortion * self.noise.fbm(p, self.octaves, 2.0, 0.5);
+        let t = 0.5 * (1.0 + (2.0 * PI * (radius * self.rings + wobble)).sin());
+        self.light * (1.0 - t) + self.dark * t
+    }
+}
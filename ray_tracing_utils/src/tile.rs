@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::math::Color;
+
+/// Rectangular block of pixels; `x`, `y` is its top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Splits a frame into tiles and shades them on a pool of worker threads.
+/// Workers pull tiles from a shared counter, so fast and slow regions of the
+/// image balance out on their own.
+#[derive(Debug, Clone)]
+pub struct TileRenderer {
+    pub tile_size: u32,
+    pub threads: usize,
+}
+
+impl Default for TileRenderer {
+    fn default() -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        TileRenderer { tile_size: 16, threads }
+    }
+}
+
+impl TileRenderer {
+    pub fn new(tile_size: u32, threads: usize) -> Self {
+        TileRenderer { tile_size, threads }
+    }
+
+    /// Tiles covering a `width` x `height` frame in scanline order.
+    pub fn tiles(&self, width: u32, height: u32) -> Vec<Tile> {
+        let size = self.tile_size.max(1);
+        let mut tiles = vec![];
+        for y in (0..height).step_by(size as usize) {
+            for x in (0..width).step_by(size as usize) {
+                tiles.push(Tile {
+                    x, y,
+                    width: size.min(width - x),
+                    height: size.min(height - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// Calls `shade(x, y)` for every pixel and returns the colors row by row,
+    /// starting from the top-left corner.
+    pub fn render<F>(&self, width: u32, height: u32, shade: F) -> Vec<Color>
+    where
+        F: Fn(u32, u32) -> Color + Sync,
+    {
+        self.render_with_progress(width, height, shade, |_| ())
+    }
+
+    /// Same as `render`, calling `progress` on the worker thread after each
+    /// tile is finished.
+    pub fn render_with_progress<F, P>(&self, width: u32, height: u32, shade: F, progress: P) -> Vec<Color>
+    where
+        F: Fn(u32, u32) -> Color + Sync,
+        P: Fn(&Tile) + Sync,
+    {
+        let tiles = self.tiles(width, height);
+        let next_tile = AtomicUsize::new(0);
+        let mut framebuffer = vec![Color::default(); width as usize * height as usize];
+
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel::<(Tile, Vec<Color>)>();
+
+            for _ in 0..self.threads.max(1) {
+                let sender = sender.clone();
+                let (tiles, next_tile, shade, progress) = (&tiles, &next_tile, &shade, &progress);
+                scope.spawn(move || {
+                    loop {
+                        let k = next_tile.fetch_add(1, Ordering::Relaxed);
+                        let Some(&tile) = tiles.get(k) else { break };
+
+                        let mut colors = Vec::with_capacity(tile.width as usize * tile.height as usize);
+                        for y in tile.y..tile.y + tile.height {
+                            for x in tile.x..tile.x + tile.width {
+                                colors.push(shade(x, y));
+                            }
+                        }
+                        progress(&tile);
+
+                        if sender.send((tile, colors)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            for (tile, colors) in receiver {
+                for (row, line) in colors.chunks_exact(tile.width as usize).enumerate() {
+                    let start = (tile.y as usize + row) * width as usize + tile.x as usize;
+                    framebuffer[start..start + line.len()].copy_from_slice(line);
+                }
+            }
+        });
+
+        framebuffer
+    }
+}
@@ -0,0 +1,257 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::math::{Vec3, Point3};
+use crate::aabb::Aabb;
+
+// Mitsuba's grid volume format: "VOL", version 3, then little-endian
+// encoding, resolution, channel count and bounds, followed by the samples
+// with x varying fastest and z slowest.
+const MAGIC: &[u8; 3] = b"VOL";
+const VERSION: u8 = 3;
+const HEADER_SIZE: usize = 48;
+const ENCODING_F32: i32 = 1;
+const ENCODING_U8: i32 = 3;
+
+/// Dense grid of scalar samples, such as the density a fluid simulation
+/// writes out, stretched over `bounds`. Samples sit at cell centers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelGrid {
+    resolution: [usize; 3],
+    bounds: Aabb,
+    data: Vec<f32>,
+    max: f32,
+}
+
+impl VoxelGrid {
+    /// Panics unless `data` holds one finite, non-negative sample per cell,
+    /// x varying fastest.
+    pub fn new(resolution: [usize; 3], bounds: Aabb, data: Vec<f32>) -> Self {
+        assert_eq!(resolution.iter().product::<usize>(), data.len(), "grid size does not match its data");
+        assert!(resolution.iter().all(|&n| n > 0), "grid has no cells");
+        if let Some(index) = data.iter().position(|&d| !valid_sample(d)) {
+            panic!("grid sample {} is {}, expected a finite, non-negative value", index, data[index]);
+        }
+        let max = data.iter().fold(0.0f32, |acc, &d| acc.max(d));
+        VoxelGrid { resolution, bounds, data, max }
+    }
+
+    /// Fills the grid by calling `f` with the center of every cell.
+    pub fn from_fn<F: FnMut(Point3) -> f32>(resolution: [usize; 3], bounds: Aabb, mut f: F) -> Self {
+        let [nx, ny, nz] = resolution;
+        let size = bounds.extent();
+        let mut data = Vec::with_capacity(nx * ny * nz);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let cell = Vec3::new(
+                        (x as f32 + 0.5) / nx as f32 * size.x,
+                        (y as f32 + 0.5) / ny as f32 * size.y,
+                        (z as f32 + 0.5) / nz as f32 * size.z,
+                    );
+                    data.push(f(bounds.min + cell));
+                }
+            }
+        }
+        VoxelGrid::new(resolution, bounds, data)
+    }
+
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    /// Largest sample.
+    pub fn max_value(&self) -> f32 {
+        self.max
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> f32 {
+        let [nx, ny, _] = self.resolution;
+        self.data[(z * ny + y) * nx + x]
+    }
+
+    /// Trilinearly interpolated value at `p`, held constant over the outer
+    /// half cell and 0 outside the bounds.
+    pub fn sample(&self, p: Point3) -> f32 {
+        let (min, max) = (self.bounds.min, self.bounds.max);
+        if p.x < min.x || p.y < min.y || p.z < min.z || p.x > max.x || p.y > max.y || p.z > max.z {
+            return 0.0;
+        }
+
+        let size = self.bounds.extent();
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        let mut frac = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.resolution[axis];
+            let x = ((p[axis] - min[axis]) / size[axis] * n as f32 - 0.5).clamp(0.0, (n - 1) as f32);
+            let i = (x as usize).min(n - 1);
+            lower[axis] = i;
+            upper[axis] = (i + 1).min(n - 1);
+            frac[axis] = x - i as f32;
+        }
+
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let row = |y: usize, z: usize| lerp(self.get(lower[0], y, z), self.get(upper[0], y, z), frac[0]);
+        let plane = |z: usize| lerp(row(lower[1], z), row(upper[1], z), frac[1]);
+        lerp(plane(lower[2]), plane(upper[2]), frac[2])
+    }
+}
+
+#[derive(Debug)]
+pub enum VoxelError {
+    Io(io::Error),
+    /// The file does not start with `VOL` and version 3.
+    BadMagic,
+    /// An encoding other than 32-bit float (1) or 8-bit unsigned (3).
+    UnsupportedEncoding(i32),
+    /// A resolution or channel count the grid cannot hold.
+    InvalidHeader(String),
+    /// The data ended before every sample was read.
+    UnexpectedEof,
+    /// A negative, infinite or NaN sample, which no density can be.
+    InvalidSample { index: usize, value: f32 },
+}
+
+impl fmt::Display for VoxelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VoxelError::Io(e) => write!(f, "I/O error: {}", e),
+            VoxelError::BadMagic => write!(f, "not a version 3 VOL file"),
+            VoxelError::UnsupportedEncoding(encoding) => {
+                write!(f, "unsupported encoding {}, expected 1 (float32) or 3 (uint8)", encoding)
+            },
+            VoxelError::InvalidHeader(msg) => write!(f, "invalid header: {}", msg),
+            VoxelError::UnexpectedEof => write!(f, "unexpected end of file"),
+            VoxelError::InvalidSample { index, value } => {
+                write!(f, "sample {} is {}, expected a finite, non-negative value", index, value)
+            },
+        }
+    }
+}
+
+impl Error for VoxelError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            VoxelError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for VoxelError {
+    fn from(e: io::Error) -> Self {
+        VoxelError::Io(e)
+    }
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<VoxelGrid, VoxelError> {
+    decode(&fs::read(path)?)
+}
+
+pub fn read<R: Read>(mut reader: R) -> Result<VoxelGrid, VoxelError> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    decode(&data)
+}
+
+/// Decodes a single-channel VOL file. 8-bit samples are scaled to 0..=1.
+pub fn decode(data: &[u8]) -> Result<VoxelGrid, VoxelError> {
+    if data.len() < 4 {
+        return Err(VoxelError::UnexpectedEof);
+    }
+    if &data[..3] != MAGIC || data[3] != VERSION {
+        return Err(VoxelError::BadMagic);
+    }
+    if data.len() < HEADER_SIZE {
+        return Err(VoxelError::UnexpectedEof);
+    }
+
+    let word = |i: usize| -> [u8; 4] { data[4 + 4 * i..8 + 4 * i].try_into().unwrap() };
+    let int = |i: usize| i32::from_le_bytes(word(i));
+    let float = |i: usize| f32::from_le_bytes(word(i));
+
+    let encoding = int(0);
+    let sample_size = match encoding {
+        ENCODING_F32 => 4,
+        ENCODING_U8 => 1,
+        _ => return Err(VoxelError::UnsupportedEncoding(encoding)),
+    };
+    let (nx, ny, nz) = (int(1), int(2), int(3));
+    if nx <= 0 || ny <= 0 || nz <= 0 {
+        return Err(VoxelError::InvalidHeader(format!("resolution {}x{}x{}", nx, ny, nz)));
+    }
+    let channels = int(4);
+    if channels != 1 {
+        return Err(VoxelError::InvalidHeader(format!("{} channels, expected 1", channels)));
+    }
+    let bounds = Aabb::new(
+        Point3::new(float(5), float(6), float(7)),
+        Point3::new(float(8), float(9), float(10)),
+    );
+    if !(bounds.min.x < bounds.max.x && bounds.min.y < bounds.max.y && bounds.min.z < bounds.max.z) {
+        return Err(VoxelError::InvalidHeader("empty bounding box".to_string()));
+    }
+
+    let resolution = [nx as usize, ny as usize, nz as usize];
+    let count = resolution.iter().try_fold(1usize, |acc, &n| acc.checked_mul(n))
+        .ok_or_else(|| VoxelError::InvalidHeader(format!("resolution {}x{}x{}", nx, ny, nz)))?;
+    let body = &data[HEADER_SIZE..];
+    if body.len() / sample_size < count {
+        return Err(VoxelError::UnexpectedEof);
+    }
+
+    let samples: Vec<f32> = match encoding {
+        ENCODING_F32 => body.chunks_exact(4).take(count)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect(),
+        _ => body[..count].iter().map(|&b| b as f32 / 255.0).collect(),
+    };
+    if let Some(index) = samples.iter().position(|&d| !valid_sample(d)) {
+        return Err(VoxelError::InvalidSample { index, value: samples[index] });
+    }
+    Ok(VoxelGrid::new(resolution, bounds, samples))
+}
+
+/// Densities must be finite and non-negative: an infinite one leaves no
+/// room between tentative collisions, and NaN or negative ones break the
+/// ratio tracking weights.
+fn valid_sample(d: f32) -> bool {
+    d.is_finite() && d >= 0.0
+}
+
+/// Writes `grid` as a single-channel, 32-bit float VOL file.
+pub fn write<W: Write>(grid: &VoxelGrid, mut w: W) -> io::Result<()> {
+    w.write_all(MAGIC)?;
+    w.write_all(&[VERSION])?;
+    w.write_all(&ENCODING_F32.to_le_bytes())?;
+    for n in grid.resolution {
+        w.write_all(&(n as i32).to_le_bytes())?;
+    }
+    w.write_all(&1i32.to_le_bytes())?;
+    let Aabb { min, max } = grid.bounds;
+    for v in [min.x, min.y, min.z, max.x, max.y, max.z] {
+        w.write_all(&v.to_le_bytes())?;
+    }
+    for v in &grid.data {
+        w.write_all(&v.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn save<P: AsRef<Path>>(grid: &VoxelGrid, path: P) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    write(grid, &mut w)?;
+    w.flush()
+}
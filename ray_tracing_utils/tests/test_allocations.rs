@@ -0,0 +1,75 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+
+use ray_tracing_utils::math::{Vec3, Point3, Ray, Transform};
+use ray_tracing_utils::hittable::{Hittable, Instance};
+use ray_tracing_utils::scene::builtin;
+
+/// Counts allocations made by the current thread, so tests running in
+/// parallel don't see each other's.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_during<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn rays() -> Vec<Ray> {
+    (0..1000).map(|i| {
+        let s = i as f32 / 1000.0;
+        Ray::new(Point3::new(13.0, 2.0, 3.0), Vec3::new(-13.0 + 8.0 * s, -2.0, -3.0 + 6.0 * s))
+    }).collect()
+}
+
+#[test]
+fn hits_do_not_allocate() {
+    let scenes = [builtin::random_scene(0), builtin::cornell_box(), builtin::texture_test(0)];
+    let rays = rays();
+    for scene in &scenes {
+        let mut hits = 0;
+        let count = allocations_during(|| {
+            for ray in &rays {
+                if let Some(rec) = scene.world.hit(ray, 0.001, f32::INFINITY) {
+                    hits += 1;
+                    // Shading reads the borrowed material.
+                    rec.material.emitted(ray, &rec);
+                }
+            }
+        });
+        assert!(hits > 0);
+        assert_eq!(count, 0);
+    }
+}
+
+#[test]
+fn instance_hits_do_not_allocate() {
+    let shared: Arc<dyn Hittable> = Arc::from(builtin::random_scene(0).world);
+    let instance = Instance::new(shared, Transform::rotate_y(0.3).then(Transform::translate(Vec3::new(0.0, 1.0, 0.0))));
+    let rays = rays();
+    let count = allocations_during(|| {
+        for ray in &rays {
+            instance.hit(ray, 0.001, f32::INFINITY);
+        }
+    });
+    assert_eq!(count, 0);
+}
@@ -0,0 +1,41 @@
+use ray_tracing_utils::math::{Vec3, Point3, Ray};
+use ray_tracing_utils::scene::builtin;
+
+#[test]
+fn every_name_builds_a_scene() {
+    for name in builtin::NAMES {
+        let scene = builtin::by_name(name, 0).unwrap_or_else(|| panic!("{} is missing", name));
+        let aspect = scene.settings.aspect_ratio();
+        assert!((scene.camera.aspect_ratio() - aspect).abs() < 1e-3, "{}", name);
+    }
+    assert!(builtin::by_name("no such scene", 0).is_none());
+}
+
+#[test]
+fn random_scene_layout_follows_seed() {
+    // A ray skimming the ground through the field of small spheres.
+    let ray = Ray::new(Point3::new(-12.0, 0.2, -11.0), Vec3::new(1.0, 0.0, 0.9));
+    let hit_t = |seed| {
+        builtin::random_scene(seed).world.hit(&ray, 0.001, f32::INFINITY).map(|rec| rec.t)
+    };
+    assert_eq!(hit_t(1), hit_t(1));
+    assert_ne!(hit_t(1), hit_t(2));
+}
+
+#[test]
+fn cornell_box_is_open_only_at_the_front_and_lit_from_above() {
+    let scene = builtin::cornell_box();
+    let center = Point3::new(278.0, 450.0, 278.0);
+    for _ in 0..200 {
+        // Only the side facing the camera, at z = 0, is open.
+        let mut direction = Vec3::random_unit_vector();
+        direction.z = direction.z.abs();
+        let ray = Ray::new(center, direction);
+        assert!(scene.world.hit(&ray, 0.001, f32::INFINITY).is_some(), "{:?} escaped", ray.direction);
+    }
+
+    let up = Ray::new(center, Vec3::new(0.0, 1.0, 0.0));
+    let rec = scene.world.hit(&up, 0.001, f32::INFINITY).unwrap();
+    assert!((rec.p.y - 554.0).abs() < 1e-3);
+    assert!(rec.material.emitted(&up, &rec).x > 1.0);
+}
@@ -0,0 +1,104 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use ray_tracing_utils::aabb::Aabb;
+use ray_tracing_utils::bvh::BvhNode;
+use ray_tracing_utils::hittable::{Hittable, HittableList, Sphere};
+use ray_tracing_utils::material::Lambertian;
+use ray_tracing_utils::math::{Point3, Ray, Vec3};
+
+fn random_point(rng: &mut StdRng, extent: f32) -> Point3 {
+    Point3::new(
+        rng.gen_range(-extent..extent),
+        rng.gen_range(-extent..extent),
+        rng.gen_range(-extent..extent),
+    )
+}
+
+fn random_spheres(rng: &mut StdRng, n: usize) -> HittableList {
+    let mut list = HittableList::default();
+    for _ in 0..n {
+        list.hittables.push(Box::new(Sphere {
+            center: random_point(rng, 10.0),
+            radius: rng.gen_range(0.05..1.0),
+            material: Box::new(Lambertian::default()),
+        }));
+    }
+    list
+}
+
+#[test]
+fn bvh_matches_linear_list() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let linear = random_spheres(&mut StdRng::seed_from_u64(2), 1000);
+    let bvh = BvhNode::new(random_spheres(&mut StdRng::seed_from_u64(2), 1000));
+
+    let mut hits = 0;
+    for _ in 0..10000 {
+        let origin = random_point(&mut rng, 15.0);
+        let target = random_point(&mut rng, 10.0);
+        let ray = Ray::new(origin, target - origin);
+
+        let expected = linear.hit(&ray, 0.001, f32::INFINITY);
+        let actual = bvh.hit(&ray, 0.001, f32::INFINITY);
+        match (expected, actual) {
+            (Some(e), Some(a)) => {
+                assert_eq!(e.t, a.t);
+                assert_eq!(e.p, a.p);
+                assert_eq!(e.normal, a.normal);
+                hits += 1;
+            },
+            (None, None) => (),
+            (e, a) => panic!("mismatch: linear hit {}, bvh hit {}", e.is_some(), a.is_some()),
+        }
+    }
+    assert!(hits > 0);
+}
+
+#[test]
+fn bvh_respects_t_range() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let linear = random_spheres(&mut StdRng::seed_from_u64(4), 200);
+    let bvh = BvhNode::new(random_spheres(&mut StdRng::seed_from_u64(4), 200));
+
+    for _ in 0..2000 {
+        let origin = random_point(&mut rng, 15.0);
+        let direction = Vec3::random_range(-1.0, 1.0);
+        let ray = Ray::new(origin, direction);
+        let t_min = rng.gen_range(0.0..5.0);
+        let t_max = t_min + rng.gen_range(0.0..20.0);
+
+        let expected = linear.hit(&ray, t_min, t_max).map(|rec| rec.t);
+        let actual = bvh.hit(&ray, t_min, t_max).map(|rec| rec.t);
+        assert_eq!(expected, actual);
+    }
+}
+
+#[test]
+fn bvh_bounding_box_encloses_objects() {
+    let list = random_spheres(&mut StdRng::seed_from_u64(5), 100);
+    let expected = list.bounding_box().unwrap();
+    let bvh = BvhNode::new(list);
+    assert_eq!(bvh.bounding_box(), Some(expected));
+}
+
+#[test]
+fn empty_bvh_never_hits() {
+    let bvh = BvhNode::new(HittableList::default());
+    let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert!(bvh.hit(&ray, 0.0, f32::INFINITY).is_none());
+    assert!(bvh.bounding_box().is_none());
+}
+
+#[test]
+fn aabb_slab_test() {
+    let bbox = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+    let toward = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+    let away = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, -1.0));
+    let beside = Ray::new(Point3::new(2.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+
+    assert!(bbox.hit(&toward, 0.0, f32::INFINITY));
+    assert!(!bbox.hit(&toward, 0.0, 3.0));
+    assert!(!bbox.hit(&away, 0.0, f32::INFINITY));
+    assert!(!bbox.hit(&beside, 0.0, f32::INFINITY));
+    assert_eq!(bbox.surface_area(), 24.0);
+}
@@ -0,0 +1,29 @@
+use ray_tracing_utils::math::{Vec3, Point3};
+use ray_tracing_utils::camera::Camera;
+
+fn camera(aspect_ratio: f32) -> Camera {
+    Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        std::f32::consts::FRAC_PI_2,
+        aspect_ratio,
+        0.0,
+        1.0,
+    )
+}
+
+#[test]
+fn set_aspect_ratio_matches_new_camera() {
+    let mut widened = camera(1.0);
+    assert!((widened.aspect_ratio() - 1.0).abs() < 1e-6);
+
+    widened.set_aspect_ratio(2.0);
+    let expected = camera(2.0);
+    assert!((widened.aspect_ratio() - 2.0).abs() < 1e-6);
+    for (s, t) in [(0.0, 0.0), (1.0, 1.0), (0.5, 0.5), (0.25, 0.75)] {
+        let a = widened.get_ray(s, t).direction;
+        let b = expected.get_ray(s, t).direction;
+        assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+}
@@ -0,0 +1,99 @@
+use ray_tracing_utils::environment::{
+    Environment, GradientEnvironment, HdrEnvironment, ImageEnvironment, UniformEnvironment,
+};
+use ray_tracing_utils::image::{Image, Pixel};
+use ray_tracing_utils::math::{Color, Vec3};
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn uniform_and_gradient() {
+    let uniform = UniformEnvironment { color: Color::new(0.1, 0.2, 0.3) };
+    assert_eq!(uniform.color(Vec3::new(3.0, -1.0, 0.0)), Color::new(0.1, 0.2, 0.3));
+
+    let gradient = GradientEnvironment {
+        bottom: Color::new(1.0, 0.0, 0.0),
+        top: Color::new(0.0, 0.0, 1.0),
+    };
+    assert_close(gradient.color(Vec3::new(0.0, 5.0, 0.0)), Color::new(0.0, 0.0, 1.0));
+    assert_close(gradient.color(Vec3::new(0.0, -5.0, 0.0)), Color::new(1.0, 0.0, 0.0));
+    assert_close(gradient.color(Vec3::new(2.0, 0.0, 0.0)), Color::new(0.5, 0.0, 0.5));
+}
+
+#[test]
+fn equirectangular_mapping_round_trips() {
+    for &(u, v) in &[(0.5, 0.5), (0.1, 0.3), (0.9, 0.8), (0.25, 0.05)] {
+        let d = ImageEnvironment::uv_to_direction(u, v);
+        assert!((d.length() - 1.0).abs() < 1e-5);
+        let (u2, v2) = ImageEnvironment::direction_to_uv(d * 3.0);
+        assert!((u - u2).abs() < 1e-5 && (v - v2).abs() < 1e-5);
+    }
+    assert_close(ImageEnvironment::uv_to_direction(0.5, 0.5), Vec3::new(0.0, 0.0, -1.0));
+    assert_close(ImageEnvironment::uv_to_direction(0.75, 0.5), Vec3::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn image_lookup() {
+    let mut image = Image::new(4, 2);
+    image.data[2] = Pixel { r: 255, g: 0, b: 0, a: 255 };
+    image.data[6] = Pixel { r: 0, g: 255, b: 0, a: 255 };
+    image.data[7] = Pixel { r: 0, g: 0, b: 128, a: 255 };
+    let mut env = ImageEnvironment::from_image(&image);
+
+    // Slightly above the horizon looking down -z is texel (2, 0).
+    assert_eq!(env.color(Vec3::new(0.0, 0.1, -1.0)), Color::new(1.0, 0.0, 0.0));
+    assert_eq!(env.color(Vec3::new(0.0, -0.1, -1.0)), Color::new(0.0, 1.0, 0.0));
+    let blue = env.color(Vec3::new(1.0, -0.1, 0.0));
+    assert!((blue.z - 0.252).abs() < 1e-3);
+
+    env.intensity = 2.0;
+    assert_eq!(env.color(Vec3::new(0.0, 0.1, -1.0)), Color::new(2.0, 0.0, 0.0));
+}
+
+#[test]
+#[should_panic(expected = "environment map is empty")]
+fn empty_maps_are_rejected() {
+    ImageEnvironment::new(0, 0, vec![]);
+}
+
+fn sun_map() -> ImageEnvironment {
+    let (width, height) = (64, 32);
+    let mut texels = vec![Color::new(0.1, 0.1, 0.1); width * height];
+    texels[8 * width + 20] = Color::new(5000.0, 5000.0, 4000.0);
+    ImageEnvironment::new(width as u32, height as u32, texels)
+}
+
+#[test]
+fn hdr_environment_pdf_integrates_to_one() {
+    let env = HdrEnvironment::new(sun_map());
+    let pi = std::f32::consts::PI;
+    // Midpoint rule over a grid finer than the map, in the map's own angles.
+    let (nu, nv) = (256, 128);
+    let mut integral = 0.0;
+    for j in 0..nv {
+        for i in 0..nu {
+            let (u, v) = ((i as f32 + 0.5) / nu as f32, (j as f32 + 0.5) / nv as f32);
+            let d_omega = (2.0 * pi / nu as f32) * (pi / nv as f32) * (v * pi).sin();
+            integral += env.pdf(ImageEnvironment::uv_to_direction(u, v)) * d_omega;
+        }
+    }
+    assert!((integral - 1.0).abs() < 1e-3, "integral {}", integral);
+}
+
+#[test]
+fn hdr_environment_samples_the_sun() {
+    let env = HdrEnvironment::new(sun_map());
+    let mut hits = 0;
+    for _ in 0..1000 {
+        let (direction, color, pdf) = env.sample().unwrap();
+        assert!((direction.length() - 1.0).abs() < 1e-4);
+        assert_eq!(color, env.color(direction));
+        assert_eq!(pdf, env.pdf(direction));
+        if color.x > 1.0 {
+            hits += 1;
+        }
+    }
+    assert!(hits > 900, "only {} samples hit the sun", hits);
+}
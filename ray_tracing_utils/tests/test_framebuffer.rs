@@ -0,0 +1,85 @@
+use ray_tracing_utils::framebuffer::{Framebuffer, ResolveSettings, ToneMap};
+use ray_tracing_utils::math::Color;
+
+#[test]
+fn accumulates_sums_and_counts() {
+    let mut fb = Framebuffer::new(3, 2);
+    fb.add_sample(1, 1, Color::new(1.0, 0.0, 0.5));
+    fb.add_sample(1, 1, Color::new(0.0, 1.0, 0.5));
+    fb.add_samples(2, 0, Color::new(3.0, 3.0, 3.0), 4);
+
+    assert_eq!(fb.count(1, 1), 2);
+    assert_eq!(fb.sum(1, 1), Color::new(1.0, 1.0, 1.0));
+    assert_eq!(fb.get(1, 1), Color::new(0.5, 0.5, 0.5));
+    assert_eq!(fb.get(2, 0), Color::new(0.75, 0.75, 0.75));
+    assert_eq!(fb.count(0, 0), 0);
+    assert_eq!(fb.get(0, 0), Color::default());
+
+    let mut other = Framebuffer::new(3, 2);
+    other.add_sample(1, 1, Color::new(1.0, 1.0, 1.0));
+    fb.merge(&other);
+    assert_eq!(fb.count(1, 1), 3);
+    assert_eq!(fb.sum(1, 1), Color::new(2.0, 2.0, 2.0));
+
+    fb.clear();
+    assert_eq!(fb.count(1, 1), 0);
+}
+
+#[test]
+fn resolve_applies_gamma_and_clamping() {
+    let mut fb = Framebuffer::new(3, 1);
+    fb.add_sample(0, 0, Color::new(0.25, 0.0, 1.0));
+    fb.add_sample(1, 0, Color::new(4.0, -1.0, 0.5));
+
+    let image = fb.resolve(&ResolveSettings::default());
+    let px = image.get_pixel(0);
+    assert_eq!((px.r, px.g, px.b, px.a), (128, 0, 255, 255));
+    let px = image.get_pixel(1);
+    assert_eq!((px.r, px.g), (255, 0));
+    let px = image.get_pixel(2);
+    assert_eq!((px.r, px.g, px.b), (0, 0, 0));
+
+    let linear = ResolveSettings { gamma: 1.0, ..Default::default() };
+    assert_eq!(fb.resolve(&linear).get_pixel(0).r, 64);
+}
+
+#[test]
+fn resolve_settings_tone_mapping() {
+    let reinhard = ResolveSettings { gamma: 1.0, tone_map: ToneMap::Reinhard, ..Default::default() };
+    assert_eq!(reinhard.apply(1.0), 0.5);
+    assert!(reinhard.apply(1000.0) < 1.0);
+
+    let exposed = ResolveSettings { exposure: 2.0, gamma: 1.0, ..Default::default() };
+    assert_eq!(exposed.apply(0.25), 0.5);
+    assert_eq!(exposed.apply(0.75), 1.0);
+}
+
+#[test]
+fn unsampled_pixels_are_transparent() {
+    let mut fb = Framebuffer::new(2, 1);
+    fb.add_sample(0, 0, Color::new(1.0, 1.0, 1.0));
+
+    let image = fb.resolve(&ResolveSettings::default());
+    assert_eq!(image.get_pixel(0).a, 255);
+    assert_eq!(image.get_pixel(1).a, 0);
+}
+
+#[test]
+fn writes_16_bit_png() {
+    let mut fb = Framebuffer::new(2, 1);
+    fb.add_sample(0, 0, Color::new(1.0, 0.25, 0.0));
+
+    let mut bytes = vec![];
+    fb.write_png(&mut bytes, &ResolveSettings::default()).unwrap();
+
+    let decoder = png::Decoder::new(bytes.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    assert_eq!((info.width, info.height), (2, 1));
+    assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+
+    let channels: Vec<u16> = buf.chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]])).collect();
+    assert_eq!(channels, vec![65535, 32768, 0, 65535, 0, 0, 0, 0]);
+}
@@ -0,0 +1,153 @@
+use std::f32::consts::PI;
+
+use ray_tracing_utils::math::{Color, Point3, Ray, Vec3};
+use ray_tracing_utils::scene::gltf::{self, GltfError, GltfOptions};
+use ray_tracing_utils::scene::Scene;
+
+/// Packs JSON and a binary buffer into a `.glb` container.
+fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+    let mut json = json.as_bytes().to_vec();
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut bin = bin.to_vec();
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let mut data = vec![];
+    data.extend(b"glTF");
+    data.extend(2u32.to_le_bytes());
+    data.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+    data.extend((json.len() as u32).to_le_bytes());
+    data.extend(b"JSON");
+    data.extend(json);
+    data.extend((bin.len() as u32).to_le_bytes());
+    data.extend(b"BIN\0");
+    data.extend(bin);
+    data
+}
+
+fn triangle_buffer() -> Vec<u8> {
+    [-1.0f32, -1.0, 0.0, 1.0, -1.0, 0.0, 0.0, 1.0, 0.0].iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn load(nodes: &str, scene_nodes: &str) -> Scene {
+    let json = format!(r#"{{
+        "asset": {{"version": "2.0"}},
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": {{"KHR_lights_punctual": {{"lights": [{{"type": "point", "intensity": 10.0}}]}}}},
+        "buffers": [{{"byteLength": 36}}],
+        "bufferViews": [{{"buffer": 0, "byteLength": 36}}],
+        "accessors": [{{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                        "min": [-1, -1, 0], "max": [1, 1, 0]}}],
+        "materials": [{{"pbrMetallicRoughness": {{"baseColorFactor": [1, 0, 0, 1], "metallicFactor": 0}}}}],
+        "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "material": 0}}]}}],
+        "cameras": [{{"type": "perspective", "perspective": {{"yfov": 0.8, "znear": 0.1, "aspectRatio": 2.0}}}}],
+        "nodes": [{}],
+        "scenes": [{{"nodes": [{}]}}],
+        "scene": 0
+    }}"#, nodes, scene_nodes);
+    gltf::from_slice(&glb(&json, &triangle_buffer()), &GltfOptions::default()).unwrap()
+}
+
+const NODES: &str = r#"
+    {"mesh": 0, "translation": [0, 0, -5]},
+    {"camera": 0, "translation": [0, 0, 1]},
+    {"translation": [10, 0, -5], "scale": [2, 2, 2], "children": [3]},
+    {"mesh": 0, "translation": [1, 0, 0]},
+    {"extensions": {"KHR_lights_punctual": {"light": 0}}, "translation": [0, 3, -5]}
+"#;
+
+#[test]
+fn camera_and_materials() {
+    let scene = load(NODES, "0, 1, 2, 4");
+    assert_eq!((scene.settings.width, scene.settings.height), (384, 192));
+
+    let ray = scene.camera.get_ray(0.5, 0.5);
+    assert_eq!(ray.origin, Point3::new(0.0, 0.0, 1.0));
+    let rec = scene.world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!((rec.p.z + 5.0).abs() < 1e-4);
+    let (_, albedo) = rec.material.scatter(&ray, &rec).unwrap();
+    assert_eq!(albedo, Color::new(1.0, 0.0, 0.0));
+
+    // Lit scenes get a black environment.
+    assert_eq!(scene.settings.environment.color(Vec3::new(0.0, 1.0, 0.0)), Color::default());
+}
+
+#[test]
+fn node_hierarchy_and_lights() {
+    let scene = load(NODES, "0, 1, 2, 4");
+
+    // Child of a node scaled by 2: the local origin lands at 10 + 2 * 1.
+    let ray = Ray::new(Point3::new(12.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = scene.world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!((rec.t - 5.0).abs() < 1e-4);
+    let bbox = scene.world.bounding_box().unwrap();
+    assert!((bbox.max.x - 14.0).abs() < 1e-4);
+
+    let ray = Ray::new(Point3::new(0.0, 3.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = scene.world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    let radius = GltfOptions::default().light_radius;
+    assert!((rec.t - (5.0 - radius)).abs() < 1e-4);
+    let expected = 10.0 / (PI * radius * radius);
+    assert!((rec.material.emitted(&ray, &rec).x - expected).abs() < 1e-2 * expected);
+}
+
+#[test]
+fn scene_without_camera_is_framed() {
+    let scene = load(r#"{"mesh": 0, "translation": [3, 4, -5]}"#, "0");
+    let ray = scene.camera.get_ray(0.5, 0.5);
+    let rec = scene.world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!((rec.p.z + 5.0).abs() < 1e-4);
+    // Nothing emits, so the default sky stays on.
+    assert_ne!(scene.settings.environment.color(Vec3::new(0.0, 1.0, 0.0)), Color::default());
+}
+
+#[test]
+fn invalid_files() {
+    assert!(matches!(gltf::from_slice(b"not a gltf file", &GltfOptions::default()), Err(GltfError::Gltf(_))));
+    let json = r#"{"asset": {"version": "2.0"}}"#;
+    assert!(matches!(gltf::from_slice(json.as_bytes(), &GltfOptions::default()), Err(GltfError::NoScene)));
+}
+
+#[test]
+fn base_color_textures_are_tinted_and_use_their_texture_coordinates() {
+    // 2 x 1 image: white on the left, black on the right.
+    let mut png_data = vec![];
+    let mut encoder = png::Encoder::new(&mut png_data, 2, 1);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.write_header().unwrap().write_image_data(&[255, 255, 255, 0, 0, 0]).unwrap();
+
+    // Set 0 samples the black half and set 1 the white one.
+    let mut bin = triangle_buffer();
+    bin.extend([0.9f32; 6].iter().flat_map(|v| v.to_le_bytes()));
+    bin.extend([0.1f32; 6].iter().flat_map(|v| v.to_le_bytes()));
+    bin.extend(&png_data);
+    let json = format!(r#"{{
+        "asset": {{"version": "2.0"}},
+        "buffers": [{{"byteLength": {}}}],
+        "bufferViews": [
+            {{"buffer": 0, "byteLength": 36}},
+            {{"buffer": 0, "byteOffset": 36, "byteLength": 24}},
+            {{"buffer": 0, "byteOffset": 60, "byteLength": 24}},
+            {{"buffer": 0, "byteOffset": 84, "byteLength": {}}}
+        ],
+        "accessors": [
+            {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [-1, -1, 0], "max": [1, 1, 0]}},
+            {{"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2"}},
+            {{"bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC2"}}
+        ],
+        "images": [{{"bufferView": 3, "mimeType": "image/png"}}],
+        "textures": [{{"source": 0}}],
+        "materials": [{{"pbrMetallicRoughness": {{
+            "baseColorFactor": [0.5, 0.25, 1, 1], "metallicFactor": 0,
+            "baseColorTexture": {{"index": 0, "texCoord": 1}}
+        }}}}],
+        "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0, "TEXCOORD_0": 1, "TEXCOORD_1": 2}}, "material": 0}}]}}],
+        "nodes": [{{"mesh": 0}}],
+        "scenes": [{{"nodes": [0]}}]
+    }}"#, 84 + png_data.len(), png_data.len());
+    let scene = gltf::from_slice(&glb(&json, &bin), &GltfOptions::default()).unwrap();
+
+    let ray = Ray::new(Point3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = scene.world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    let (_, albedo) = rec.material.scatter(&ray, &rec).unwrap();
+    assert_eq!(albedo, Color::new(0.5, 0.25, 1.0));
+}
@@ -0,0 +1,109 @@
+use ray_tracing_utils::hdr::{decode, HdrError, HdrImage};
+use ray_tracing_utils::math::Color;
+
+fn assert_close(a: Color, b: Color) {
+    let tolerance = 0.01 * b.x.max(b.y).max(b.z).max(1e-3);
+    assert!((a - b).length() <= tolerance, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn round_trip() {
+    let mut image = HdrImage::new(3, 2);
+    image.data = vec![
+        Color::new(0.0, 0.0, 0.0),
+        Color::new(1.0, 0.5, 0.25),
+        Color::new(1000.0, 2.0, 0.001),
+        Color::new(0.01, 0.02, 0.03),
+        Color::new(65504.0, 1.0, 1.0),
+        Color::new(3.0, 3.0, 3.0),
+    ];
+
+    let mut bytes = vec![];
+    image.write_hdr(&mut bytes).unwrap();
+    let read = decode(&bytes).unwrap();
+
+    assert_eq!((read.width, read.height), (3, 2));
+    assert_eq!(read.get_pixel(0, 0), Color::default());
+    for (a, b) in read.data.iter().zip(image.data.iter()).skip(1) {
+        assert_close(*a, *b);
+    }
+}
+
+#[test]
+fn infinite_channels_saturate() {
+    let mut image = HdrImage::new(2, 1);
+    image.data = vec![Color::new(f32::INFINITY, 1.0, 0.0), Color::new(0.5, 0.5, 0.5)];
+
+    let mut bytes = vec![];
+    image.write_hdr(&mut bytes).unwrap();
+    let read = decode(&bytes).unwrap();
+
+    // The exponent tops out at 2^127, so the other channels drop to the
+    // smallest mantissa.
+    let saturated = read.get_pixel(0, 0);
+    assert!(saturated.x.is_finite() && saturated.x > 1e38, "{:?}", saturated);
+    assert!(saturated.y < saturated.x / 256.0 && saturated.z < saturated.x / 256.0);
+    assert_close(read.get_pixel(1, 0), Color::new(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn new_style_run_length_encoding() {
+    let mut bytes = b"#?RADIANCE\n# comment\nEXPOSURE=1.0\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+    bytes.extend_from_slice(&[2, 2, 0, 8]);
+    // Red: run of 8 x 128. Green: literal 8 values. Blue: two runs. Exponent: run of 8 x 129.
+    bytes.extend_from_slice(&[128 + 8, 128]);
+    bytes.extend_from_slice(&[8, 0, 16, 32, 48, 64, 80, 96, 112]);
+    bytes.extend_from_slice(&[128 + 4, 0, 128 + 4, 255]);
+    bytes.extend_from_slice(&[128 + 8, 129]);
+
+    let image = decode(&bytes).unwrap();
+    assert_eq!((image.width, image.height), (8, 1));
+    // Mantissa m with exponent 129 decodes to (m + 0.5) / 128.
+    assert_close(image.get_pixel(0, 0), Color::new(128.5, 0.5, 0.5) / 128.0);
+    assert_close(image.get_pixel(7, 0), Color::new(128.5, 112.5, 255.5) / 128.0);
+}
+
+#[test]
+fn old_style_runs_and_bottom_up_rows() {
+    let mut bytes = b"#?RGBE\n\n+Y 2 +X 3\n".to_vec();
+    // Bottom row: one pixel repeated twice with an old-style run.
+    bytes.extend_from_slice(&[128, 0, 0, 129, 1, 1, 1, 2]);
+    // Top row: flat pixels.
+    bytes.extend_from_slice(&[0, 128, 0, 129, 0, 0, 128, 129, 0, 0, 0, 0]);
+
+    let image = decode(&bytes).unwrap();
+    assert_close(image.get_pixel(0, 0), Color::new(0.5, 128.5, 0.5) / 128.0);
+    assert_eq!(image.get_pixel(2, 0), Color::default());
+    for x in 0..3 {
+        assert_close(image.get_pixel(x, 1), Color::new(128.5, 0.5, 0.5) / 128.0);
+    }
+}
+
+#[test]
+fn errors_are_reported() {
+    assert!(matches!(decode(b"P6\n"), Err(HdrError::BadSignature)));
+    assert!(matches!(
+        decode(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n"),
+        Err(HdrError::UnsupportedFormat(_))
+    ));
+    assert!(matches!(decode(b"#?RADIANCE\n\n+X 1 -Y 1\n"), Err(HdrError::BadResolution(_))));
+    assert!(matches!(decode(b"#?RADIANCE\n\n-Y 0 +X 0\n"), Err(HdrError::BadResolution(_))));
+    assert!(matches!(decode(b"#?RADIANCE\n\n-Y 2 +X 0\n"), Err(HdrError::BadResolution(_))));
+    assert!(matches!(decode(b"#?RADIANCE\n\n-Y 1 +X 2\n\x80\x80\x80\x80"), Err(HdrError::UnexpectedEof)));
+
+    let mut bad_run = b"#?RADIANCE\n\n-Y 1 +X 8\n".to_vec();
+    bad_run.extend_from_slice(&[2, 2, 0, 8, 128 + 9, 0]);
+    assert!(matches!(decode(&bad_run), Err(HdrError::BadScanline(0))));
+}
+
+#[test]
+fn lying_headers_fail_without_allocating_the_claimed_size() {
+    // 65535 x 65535 pixels would be 48 GiB of colors; the file holds one pixel.
+    let lying = b"#?RADIANCE\n\n-Y 65535 +X 65535\n\x80\x80\x80\x81";
+    assert!(matches!(decode(lying), Err(HdrError::UnexpectedEof)));
+
+    // Enough bytes for one value per row, but not for the claimed width.
+    let mut short_rows = b"#?RADIANCE\n\n-Y 4 +X 4000000000\n".to_vec();
+    short_rows.extend_from_slice(&[128, 0, 0, 129].repeat(4));
+    assert!(matches!(decode(&short_rows), Err(HdrError::UnexpectedEof)));
+}
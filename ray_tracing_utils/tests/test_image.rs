@@ -0,0 +1,23 @@
+use ray_tracing_utils::image::{Image, Pixel};
+
+#[test]
+fn png_round_trip_keeps_alpha() {
+    let mut image = Image::new(3, 2);
+    image.data[0] = Pixel { r: 255, g: 0, b: 0, a: 255 };
+    image.data[4] = Pixel { r: 10, g: 20, b: 30, a: 128 };
+    image.data[5] = Pixel { r: 0, g: 0, b: 0, a: 0 };
+
+    let mut bytes = vec![];
+    image.write_png(&mut bytes).unwrap();
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+
+    let decoder = png::Decoder::new(bytes.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    assert_eq!((info.width, info.height), (3, 2));
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+
+    let expected: Vec<u8> = image.data.iter().flat_map(|px| [px.r, px.g, px.b, px.a]).collect();
+    assert_eq!(buf, expected);
+}
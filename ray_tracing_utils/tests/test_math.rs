@@ -19,7 +19,7 @@ fn vector_calculation() {
     assert_eq!(c * -0.5, Vec3::new(-1.5, 1.5, -1.5));
     assert_eq!(c / 2.0, Vec3::new(1.5, -1.5, 1.5));
 
-    assert_eq!(a * b, 26.0);
-    assert_eq!(a * c, 6.0);
-    assert_eq!(b * c, 9.0);
+    assert_eq!(Vec3::dot(a, b), 26.0);
+    assert_eq!(Vec3::dot(a, c), 6.0);
+    assert_eq!(Vec3::dot(b, c), 9.0);
 }
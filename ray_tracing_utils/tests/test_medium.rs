@@ -0,0 +1,199 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use ray_tracing_utils::math::{Vec3, Point3, Color, Ray};
+use ray_tracing_utils::aabb::Aabb;
+use ray_tracing_utils::hittable::{Hittable, HittableList, Sphere};
+use ray_tracing_utils::bvh::BvhNode;
+use ray_tracing_utils::material::{Material, Lambertian, Isotropic, HenyeyGreenstein};
+use ray_tracing_utils::medium::{ConstantMedium, GridMedium};
+use ray_tracing_utils::voxel::VoxelGrid;
+
+fn fog(density: f32) -> ConstantMedium {
+    ConstantMedium::new(
+        Box::new(Sphere { center: Point3::default(), radius: 1.0, material: Box::new(Lambertian::default()) }),
+        density,
+        Box::new(Isotropic::new(Color::new(0.5, 0.5, 0.5))),
+    )
+}
+
+#[test]
+fn henyey_greenstein_is_normalized_with_mean_cosine_g() {
+    for g in [-0.7, 0.0, 0.3, 0.9] {
+        let n = 100_000;
+        let mut integral = 0.0;
+        let mut mean = 0.0;
+        for i in 0..n {
+            let u = (i as f32 + 0.5) / n as f32;
+            // Over the sphere, d(solid angle) = 2 pi d(cos theta).
+            integral += HenyeyGreenstein::phase(g, 2.0 * u - 1.0) * 2.0 * PI * 2.0 / n as f32;
+            mean += HenyeyGreenstein::sample_cos_theta(g, u) / n as f32;
+        }
+        assert!((integral - 1.0).abs() < 1e-2, "g = {}: integral {}", g, integral);
+        assert!((mean - g).abs() < 1e-2, "g = {}: mean cosine {}", g, mean);
+    }
+}
+
+#[test]
+fn henyey_greenstein_scatters_forward() {
+    let material = HenyeyGreenstein::new(Color::new(1.0, 1.0, 1.0), 0.8);
+    let medium = ConstantMedium::new(
+        Box::new(Sphere { center: Point3::default(), radius: 1.0, material: Box::new(Lambertian::default()) }),
+        1000.0,
+        Box::new(material.clone()),
+    );
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -2.0));
+    let rec = medium.hit(&ray, 0.001, f32::INFINITY).unwrap();
+
+    let n = 20_000;
+    let mean: f32 = (0..n).map(|_| {
+        let (scattered, attenuation) = material.scatter(&ray, &rec).unwrap();
+        assert_eq!(attenuation, Color::new(1.0, 1.0, 1.0));
+        assert!((scattered.direction.length() - 1.0).abs() < 1e-4);
+        -scattered.direction.z
+    }).sum::<f32>() / n as f32;
+    assert!((mean - 0.8).abs() < 0.02, "{}", mean);
+
+    let (f, pdf) = material.scattering(&ray, &rec, Vec3::new(0.0, 0.0, -1.0)).unwrap();
+    assert!((pdf - HenyeyGreenstein::phase(0.8, 1.0)).abs() < 1e-3);
+    assert_eq!(f, Color::new(pdf, pdf, pdf));
+}
+
+#[test]
+fn isotropic_scattering_is_uniform() {
+    let medium = fog(1000.0);
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = medium.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    let (f, pdf) = rec.material.scattering(&ray, &rec, Vec3::new(1.0, 0.0, 0.0)).unwrap();
+    assert!((pdf - 1.0 / (4.0 * PI)).abs() < 1e-6);
+    assert!((f.x - 0.5 * pdf).abs() < 1e-6);
+}
+
+#[test]
+fn transmittance_follows_beer_lambert() {
+    // Through the middle of the unit sphere: two units at density 0.5.
+    let medium = fog(0.5);
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -3.0));
+    let n = 20_000;
+    let mut passed = 0;
+    for _ in 0..n {
+        match medium.hit(&ray, 0.001, f32::INFINITY) {
+            Some(rec) => {
+                assert!(rec.p.z <= 1.0 + 1e-4 && rec.p.z >= -1.0 - 1e-4, "{:?}", rec.p);
+                assert!(rec.front_face);
+            },
+            None => passed += 1,
+        }
+    }
+    let fraction = passed as f32 / n as f32;
+    assert!((fraction - (-1.0f32).exp()).abs() < 0.02, "{}", fraction);
+}
+
+#[test]
+fn rays_starting_inside_or_clipped_short() {
+    let medium = fog(0.5);
+    // From the center only one unit of fog is left.
+    let inside = Ray::new(Point3::default(), Vec3::new(1.0, 0.0, 0.0));
+    let n = 20_000;
+    let passed = (0..n).filter(|_| medium.hit(&inside, 0.001, f32::INFINITY).is_none()).count();
+    assert!((passed as f32 / n as f32 - (-0.5f32).exp()).abs() < 0.02);
+
+    // Nothing past t_max, and nothing for rays missing the boundary.
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!((0..1000).all(|_| medium.hit(&ray, 0.001, 4.0).is_none()));
+    let miss = Ray::new(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(medium.hit(&miss, 0.001, f32::INFINITY).is_none());
+
+    let bbox = medium.bounding_box().unwrap();
+    assert_eq!(bbox.min, Point3::new(-1.0, -1.0, -1.0));
+}
+
+/// Unit cube from the origin whose density rises linearly along x from 0
+/// to `density`.
+fn ramp(density: f32) -> GridMedium {
+    let bounds = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+    let grid = VoxelGrid::from_fn([16, 2, 2], bounds, |p| p.x);
+    GridMedium::new(Arc::new(grid), density, Color::new(0.25, 0.5, 1.0))
+}
+
+#[test]
+fn grid_transmittance_matches_optical_depth() {
+    let medium = ramp(2.0);
+    // Along x the optical depth is the integral of 2x, about 1, give or
+    // take the clamped half cells at either end.
+    let ray = Ray::new(Point3::new(-1.0, 0.5, 0.5), Vec3::new(2.0, 0.0, 0.0));
+    let expected = (-1.0f32).exp();
+
+    let n = 20_000;
+    let ratio = (0..n).map(|_| medium.transmittance(&ray, 0.001, f32::INFINITY)).sum::<f32>() / n as f32;
+    assert!((ratio - expected).abs() < 0.02, "ratio tracking {}", ratio);
+
+    let passed = (0..n).filter(|_| medium.hit(&ray, 0.001, f32::INFINITY).is_none()).count();
+    let delta = passed as f32 / n as f32;
+    assert!((delta - expected).abs() < 0.02, "delta tracking {}", delta);
+
+    // Constant in y, so a ray through the thinnest end barely dims.
+    let thin = Ray::new(Point3::new(0.01, -1.0, 0.5), Vec3::new(0.0, 1.0, 0.0));
+    let thin = (0..n).map(|_| medium.transmittance(&thin, 0.001, f32::INFINITY)).sum::<f32>() / n as f32;
+    assert!(thin > 0.9, "{}", thin);
+}
+
+#[test]
+fn grid_collisions_follow_the_density() {
+    let medium = ramp(2.0);
+    let ray = Ray::new(Point3::new(-1.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0));
+    let n = 20_000;
+    let mut left = 0;
+    let mut right = 0;
+    for _ in 0..n {
+        if let Some(rec) = medium.hit(&ray, 0.001, f32::INFINITY) {
+            assert!((0.0..=1.0).contains(&rec.p.x), "{:?}", rec.p);
+            if rec.p.x < 0.5 { left += 1 } else { right += 1 }
+        }
+    }
+    // The first collision lies before x with probability 1 - exp(-x^2).
+    let (left, right) = (left as f32 / n as f32, right as f32 / n as f32);
+    assert!((left - (1.0 - (-0.25f32).exp())).abs() < 0.02, "{}", left);
+    assert!((right - ((-0.25f32).exp() - (-1.0f32).exp())).abs() < 0.02, "{}", right);
+
+    // Outside the grid and past t_max nothing is hit.
+    let miss = Ray::new(Point3::new(-1.0, 2.0, 0.5), Vec3::new(1.0, 0.0, 0.0));
+    assert!(medium.hit(&miss, 0.001, f32::INFINITY).is_none());
+    assert_eq!(medium.transmittance(&miss, 0.001, f32::INFINITY), 1.0);
+    assert!((0..1000).all(|_| medium.hit(&ray, 0.001, 0.99).is_none()));
+}
+
+#[test]
+fn grid_collisions_scatter_with_albedo_and_glow_where_absorbing() {
+    let medium = ramp(1000.0).with_emission(Color::new(2.0, 2.0, 2.0));
+    let ray = Ray::new(Point3::new(-1.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0));
+    let rec = medium.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    let (_, attenuation) = rec.material.scatter(&ray, &rec).unwrap();
+    assert_eq!(attenuation, Color::new(0.25, 0.5, 1.0));
+    assert_eq!(rec.material.emitted(&ray, &rec), Color::new(1.5, 1.0, 0.0));
+    assert!(rec.material.scattering(&ray, &rec, Vec3::new(0.0, 1.0, 0.0)).is_some());
+}
+
+#[test]
+fn aggregates_multiply_transmittance() {
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let single = fog(0.5).transmittance(&ray, 0.001, f32::INFINITY);
+    assert!((single - (-1.0f32).exp()).abs() < 1e-5);
+
+    let shifted = || ConstantMedium::new(
+        Box::new(Sphere { center: Point3::new(0.0, 0.0, -3.0), radius: 1.0, material: Box::new(Lambertian::default()) }),
+        0.5,
+        Box::new(Isotropic::new(Color::new(0.5, 0.5, 0.5))),
+    );
+    let list = HittableList { hittables: vec![Box::new(fog(0.5)), Box::new(shifted())] };
+    assert!((list.transmittance(&ray, 0.001, f32::INFINITY) - single * single).abs() < 1e-5);
+    // Stopping short of the second sphere leaves only the first.
+    assert!((list.transmittance(&ray, 0.001, 7.0) - single).abs() < 1e-5);
+
+    let bvh = BvhNode::new(HittableList { hittables: vec![Box::new(fog(0.5)), Box::new(shifted())] });
+    assert!((bvh.transmittance(&ray, 0.001, f32::INFINITY) - single * single).abs() < 1e-5);
+
+    let wall = Sphere { center: Point3::new(0.0, 0.0, -6.0), radius: 1.0, material: Box::new(Lambertian::default()) };
+    let blocked = BvhNode::new(HittableList { hittables: vec![Box::new(fog(0.5)), Box::new(wall)] });
+    assert_eq!(blocked.transmittance(&ray, 0.001, f32::INFINITY), 0.0);
+}
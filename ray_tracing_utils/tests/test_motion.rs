@@ -0,0 +1,200 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+use std::path::Path;
+use std::sync::Arc;
+
+use ray_tracing_utils::math::{Vec3, Point3, Ray, Transform, AnimatedTransform};
+use ray_tracing_utils::hittable::{Hittable, Instance, MovingInstance, MovingSphere, Sphere};
+use ray_tracing_utils::camera::Camera;
+use ray_tracing_utils::material::{Material, Lambertian};
+use ray_tracing_utils::scene::file;
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+}
+
+fn camera() -> Camera {
+    Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        FRAC_PI_2,
+        1.0,
+        0.0,
+        1.0,
+    )
+}
+
+#[test]
+fn camera_stamps_rays_within_shutter() {
+    assert_eq!(camera().get_ray(0.5, 0.5).time, 0.0);
+
+    let camera = camera().with_shutter(2.0, 3.0);
+    assert_eq!(camera.shutter(), (2.0, 3.0));
+    let times: Vec<f32> = (0..1000).map(|_| camera.get_ray(0.5, 0.5).time).collect();
+    assert!(times.iter().all(|t| (2.0..3.0).contains(t)));
+    let mean = times.iter().sum::<f32>() / times.len() as f32;
+    assert!((mean - 2.5).abs() < 0.05, "{}", mean);
+}
+
+fn moving_sphere() -> MovingSphere {
+    MovingSphere {
+        center0: Point3::new(0.0, 0.0, 0.0),
+        center1: Point3::new(4.0, 0.0, 0.0),
+        time0: 0.0,
+        time1: 1.0,
+        radius: 1.0,
+        material: Box::new(Lambertian::default()),
+    }
+}
+
+#[test]
+fn moving_sphere_is_hit_where_it_is_at_the_ray_time() {
+    let sphere = moving_sphere();
+    let ray_at = |x: f32, time: f32| Ray::with_time(Point3::new(x, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0), time);
+
+    assert!(sphere.hit(&ray_at(0.0, 0.0), 0.001, f32::INFINITY).is_some());
+    assert!(sphere.hit(&ray_at(4.0, 0.0), 0.001, f32::INFINITY).is_none());
+    assert!(sphere.hit(&ray_at(4.0, 1.0), 0.001, f32::INFINITY).is_some());
+
+    let rec = sphere.hit(&ray_at(2.0, 0.5), 0.001, f32::INFINITY).unwrap();
+    assert_close(rec.p, Point3::new(2.0, 0.0, 1.0));
+    assert_close(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+
+    // It rests at the ends outside its interval.
+    assert_close(sphere.center(-1.0), Point3::new(0.0, 0.0, 0.0));
+    assert_close(sphere.center(5.0), Point3::new(4.0, 0.0, 0.0));
+
+    let bbox = sphere.bounding_box().unwrap();
+    assert_close(bbox.min, Point3::new(-1.0, -1.0, -1.0));
+    assert_close(bbox.max, Point3::new(5.0, 1.0, 1.0));
+}
+
+#[test]
+fn scattered_and_transformed_rays_keep_their_time() {
+    let sphere = Sphere { center: Point3::default(), radius: 1.0, material: Box::new(Lambertian::default()) };
+    let ray = Ray::with_time(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.75);
+    let rec = sphere.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    let (scattered, _) = Lambertian::default().scatter(&ray, &rec).unwrap();
+    assert_eq!(scattered.time, 0.75);
+
+    assert_eq!(Transform::translate(Vec3::new(1.0, 2.0, 3.0)).transform_ray(&ray).time, 0.75);
+
+    // An instance of the moving sphere sees the ray's time too.
+    let instance = Instance::new(Arc::new(moving_sphere()), Transform::translate(Vec3::new(0.0, 5.0, 0.0)));
+    let late = Ray::with_time(Point3::new(4.0, 5.0, 10.0), Vec3::new(0.0, 0.0, -1.0), 1.0);
+    assert!(instance.hit(&late, 0.001, f32::INFINITY).is_some());
+}
+
+#[test]
+fn animated_transform_interpolates_rigidly() {
+    let start = Transform::scale(Vec3::new(1.0, 2.0, 1.0));
+    let end = Transform::scale(Vec3::new(3.0, 2.0, 1.0))
+        .then(Transform::rotate_y(FRAC_PI_2))
+        .then(Transform::translate(Vec3::new(10.0, 0.0, 0.0)));
+    let motion = AnimatedTransform::new(start, 0.0, end, 2.0);
+    assert!(motion.is_animated());
+    assert!((motion.rotation_angle() - FRAC_PI_2).abs() < 1e-4);
+
+    assert_eq!(motion.at(-1.0), start);
+    assert_eq!(motion.at(0.0), start);
+    assert_eq!(motion.at(2.0), end);
+    assert_eq!(motion.at(3.0), end);
+
+    let expected = Transform::scale(Vec3::new(2.0, 2.0, 1.0))
+        .then(Transform::rotate_y(FRAC_PI_4))
+        .then(Transform::translate(Vec3::new(5.0, 0.0, 0.0)));
+    let halfway = motion.at(1.0);
+    for p in [Point3::new(1.0, 0.0, 0.0), Point3::new(0.0, 1.0, 0.0), Point3::new(0.3, -0.2, 0.9)] {
+        assert_close(halfway.transform_point(p), expected.transform_point(p));
+        assert_close(halfway.inverse().transform_point(halfway.transform_point(p)), p);
+    }
+
+    // A mirror in both keyframes stays a mirror in between.
+    let mirror = Transform::scale(Vec3::new(-1.0, 1.0, 1.0));
+    let mirrored = AnimatedTransform::new(mirror, 0.0, mirror.then(Transform::rotate_z(1.0)), 1.0);
+    assert!(mirrored.at(0.5).determinant() < 0.0);
+    assert!(!AnimatedTransform::fixed(mirror).is_animated());
+}
+
+#[test]
+fn moving_instance_bounds_cover_the_whole_motion() {
+    let object: Arc<dyn Hittable> = Arc::new(Sphere {
+        center: Point3::new(3.0, 0.0, 0.0),
+        radius: 0.5,
+        material: Box::new(Lambertian::default()),
+    });
+    // Swings the sphere half a turn around the y axis while moving up.
+    let motion = AnimatedTransform::new(
+        Transform::identity(), 0.0,
+        Transform::rotate_y(3.0).then(Transform::translate(Vec3::new(0.0, 2.0, 0.0))), 1.0,
+    );
+    let instance = MovingInstance::new(object.clone(), motion);
+    let bbox = instance.bounding_box().unwrap();
+    let local = object.bounding_box().unwrap();
+
+    for i in 0..=1000 {
+        let transform = motion.at(i as f32 / 1000.0);
+        for corner in local.corners() {
+            let p = transform.transform_point(corner);
+            for axis in 0..3 {
+                assert!(bbox.min[axis] <= p[axis] && p[axis] <= bbox.max[axis], "{:?} outside {:?}", p, bbox);
+            }
+        }
+    }
+
+    // Hit at the sphere's position at mid-shutter: rotated 1.5 rad, raised 1.
+    let center = motion.at(0.5).transform_point(Point3::new(3.0, 0.0, 0.0));
+    let ray = Ray::with_time(center + Vec3::new(0.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.5);
+    let rec = instance.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert_close(rec.p, center + Vec3::new(0.0, 0.5, 0.0));
+    assert_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+    let early = Ray::with_time(ray.origin, ray.direction, 0.0);
+    assert!(instance.hit(&early, 0.001, f32::INFINITY).is_none());
+}
+
+#[test]
+fn scene_file_shutter_and_moving_sphere() {
+    let text = r#"
+        [camera]
+        lookfrom = [0, 0, 5]
+        lookat = [0, 0, 0]
+        vfov = 40
+        shutter = [0, 1]
+
+        [materials.red]
+        type = "lambertian"
+        albedo = [0.8, 0.1, 0.1]
+
+        [[objects]]
+        type = "sphere"
+        center = [0, 0, 0]
+        center1 = [2, 0, 0]
+        radius = 0.5
+        material = "red"
+    "#;
+    let scene = file::from_toml(text, Path::new("")).unwrap();
+    assert_eq!(scene.camera.shutter(), (0.0, 1.0));
+    let late = Ray::with_time(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 1.0);
+    assert!(scene.world.hit(&late, 0.001, f32::INFINITY).is_some());
+
+    // The sphere moves over whatever interval the shutter is open.
+    let later = text.replace("shutter = [0, 1]", "shutter = [2, 3]");
+    let scene = file::from_toml(&later, Path::new("")).unwrap();
+    let end = Ray::with_time(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 3.0);
+    assert!(scene.world.hit(&end, 0.001, f32::INFINITY).is_some());
+    let mid = Ray::with_time(Point3::new(1.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 2.5);
+    assert!(scene.world.hit(&mid, 0.001, f32::INFINITY).is_some());
+
+    // Unless it has its own times.
+    let own = later.replace("radius = 0.5", "radius = 0.5\ntime0 = 2\ntime1 = 2.5");
+    let scene = file::from_toml(&own, Path::new("")).unwrap();
+    let end = Ray::with_time(Point3::new(2.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 2.5);
+    assert!(scene.world.hit(&end, 0.001, f32::INFINITY).is_some());
+
+    let bad = text.replace("shutter = [0, 1]", "shutter = [1, 0]");
+    let error = file::from_toml(&bad, Path::new("")).err().unwrap();
+    assert_eq!(error.to_string(), "camera.shutter: must not close before it opens");
+    let bad = own.replace("time1 = 2.5", "time1 = 1");
+    let error = file::from_toml(&bad, Path::new("")).err().unwrap();
+    assert_eq!(error.to_string(), "objects[0].time1: must not be before time0");
+}
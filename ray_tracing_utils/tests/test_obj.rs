@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::Path;
+
+use ray_tracing_utils::hittable::Hittable;
+use ray_tracing_utils::image::{Image, Pixel};
+use ray_tracing_utils::math::{Color, Point3, Ray, Vec3};
+use ray_tracing_utils::mesh::obj::{self, MtlKind, ObjError};
+use ray_tracing_utils::mesh::TriangleMesh;
+
+const QUAD: &str = "\
+# unit quad in the xy plane, split into two triangles
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+";
+
+fn down(x: f32, y: f32) -> Ray {
+    Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0))
+}
+
+#[test]
+fn polygon_is_triangulated_with_shared_buffers() {
+    let mesh = obj::read(QUAD.as_bytes()).unwrap();
+    assert_eq!(mesh.len(), 2);
+    assert_eq!(mesh.positions().len(), 4);
+    assert_eq!(mesh.normals().len(), 1);
+    assert_eq!(mesh.materials().len(), 1);
+    assert_eq!(mesh.triangles()[1].positions, [0, 2, 3]);
+    assert_eq!(mesh.triangles()[1].normals, Some([0, 0, 0]));
+
+    let bbox = mesh.bounding_box().unwrap();
+    assert_eq!((bbox.min, bbox.max), (Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 0.0)));
+
+    let rec = mesh.hit(&down(0.25, 0.75), 0.001, f32::INFINITY).unwrap();
+    assert!((rec.t - 1.0).abs() < 1e-6);
+    assert!((rec.u - 0.25).abs() < 1e-5 && (rec.v - 0.75).abs() < 1e-5);
+    assert!(rec.front_face);
+    assert!(mesh.hit(&down(1.5, 0.5), 0.001, f32::INFINITY).is_none());
+}
+
+#[test]
+fn negative_indices_and_partial_attributes() {
+    let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf -3//1 -2//1 -1\n";
+    let mesh = obj::read(source.as_bytes()).unwrap();
+    let tri = mesh.triangles()[0];
+    assert_eq!(tri.positions, [0, 1, 2]);
+    // One corner without a normal makes the whole triangle flat shaded.
+    assert_eq!(tri.normals, None);
+    assert_eq!(tri.uvs, None);
+}
+
+fn parse_error(source: &str) -> (usize, String) {
+    match obj::read(source.as_bytes()) {
+        Err(ObjError::Parse { line, message, .. }) => (line, message),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("{:?} should not parse", source),
+    }
+}
+
+#[test]
+fn malformed_lines_report_line_numbers() {
+    assert_eq!(parse_error("v 0 0 0\nv 1 x 0\n").0, 2);
+    assert_eq!(parse_error("v 0 0\n").0, 1);
+    let (line, message) = parse_error("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n");
+    assert_eq!(line, 4);
+    assert!(message.contains("out of range"), "{}", message);
+    assert_eq!(parse_error("v 0 0 0\nf 1 1\n").0, 2);
+    assert_eq!(parse_error("v 0 0 0\nf 0 1 1\n").0, 2);
+    assert_eq!(parse_error(&format!("{}f 1/9 2 3\n", QUAD)).0, 12);
+
+    let err = obj::read("# header\nvn 1 1\n".as_bytes()).err().unwrap();
+    assert!(err.to_string().starts_with("<obj>:2: "), "{}", err);
+}
+
+fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("ray_tracing_obj_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+const LIBRARY: &str = "\
+newmtl matte
+Kd 0.1 0.2 0.3
+
+newmtl chrome
+Kd 0 0 0
+Ks 0.9 0.9 0.9
+Ns 200
+
+newmtl glass
+illum 7
+Ni 1.33
+
+newmtl lamp
+Ke 4 4 4
+
+newmtl painted
+map_Kd -s 1 1 1 paint.ppm
+";
+
+fn write_scene(dir: &Path) {
+    let mut image = Image::new(1, 1);
+    image.data[0] = Pixel { r: 255, g: 0, b: 0, a: 255 };
+    image.save_ppm(dir.join("paint.ppm"), Default::default()).unwrap();
+    fs::write(dir.join("scene.mtl"), LIBRARY).unwrap();
+    let obj = "mtllib scene.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\n\
+               usemtl lamp\nf 1 2 3\nusemtl painted\nf 1 2 3\nusemtl missing\nf 1 2 3\nusemtl lamp\nf 1 2 3\n";
+    fs::write(dir.join("scene.obj"), obj).unwrap();
+}
+
+#[test]
+fn mtl_materials_are_classified() {
+    let materials = obj::parse_mtl(LIBRARY, "scene.mtl", Path::new("missing")).err().unwrap();
+    // The texture has not been written yet.
+    assert!(materials.to_string().starts_with("scene.mtl:17: cannot load texture"), "{}", materials);
+
+    let dir = scratch_dir("kinds");
+    write_scene(&dir);
+    let materials = obj::load_mtl(dir.join("scene.mtl")).unwrap();
+    let kinds: Vec<MtlKind> = materials.iter().map(|m| m.kind()).collect();
+    assert_eq!(kinds, [MtlKind::Diffuse, MtlKind::Metal, MtlKind::Glass, MtlKind::Light, MtlKind::Diffuse]);
+    assert_eq!(materials[0].diffuse, Color::new(0.1, 0.2, 0.3));
+    assert_eq!(materials[2].ior, 1.33);
+    assert!(materials[4].diffuse_map.is_some());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn load_resolves_materials_and_textures() {
+    let dir = scratch_dir("load");
+    write_scene(&dir);
+    let mesh = TriangleMesh::from_obj(dir.join("scene.obj")).unwrap();
+    assert_eq!(mesh.len(), 4);
+    // Lamp, painted and the default; the second lamp face reuses its slot.
+    assert_eq!(mesh.materials().len(), 3);
+    let slots: Vec<u32> = mesh.triangles().iter().map(|t| t.material).collect();
+    assert_eq!(slots, [0, 1, 2, 0]);
+
+    let ray = Ray::new(Point3::new(0.2, 0.2, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = mesh.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert_eq!(mesh.materials()[0].emitted(&ray, &rec), Color::new(4.0, 4.0, 4.0));
+    let (_, albedo) = mesh.materials()[1].scatter(&ray, &rec).unwrap();
+    assert_eq!(albedo, Color::new(1.0, 0.0, 0.0));
+
+    fs::write(dir.join("broken.obj"), "v 0 0 0\nmtllib nowhere.mtl\n").unwrap();
+    let err = obj::load(dir.join("broken.obj")).err().unwrap();
+    assert!(matches!(err, ObjError::Parse { line: 2, .. }), "{}", err);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn png_maps_load_and_undecodable_maps_fall_back_to_kd() {
+    let dir = scratch_dir("png");
+    let mut image = Image::new(1, 1);
+    image.data[0] = Pixel { r: 0, g: 255, b: 0, a: 255 };
+    image.save_png(dir.join("leaf.png")).unwrap();
+    fs::write(dir.join("photo.jpg"), b"\xff\xd8\xff\xe0 not really a jpeg").unwrap();
+    let library = "newmtl leaf\nKd 1 0 0\nmap_Kd leaf.png\n\nnewmtl photo\nKd 0 0 1\nmap_Kd photo.jpg\n";
+    fs::write(dir.join("maps.mtl"), library).unwrap();
+
+    let materials = obj::load_mtl(dir.join("maps.mtl")).unwrap();
+    assert!(materials[0].diffuse_map.is_some());
+    assert!(materials[1].diffuse_map.is_none());
+
+    let obj = "mtllib maps.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl leaf\nf 1 2 3\nusemtl photo\nf 1 2 3\n";
+    fs::write(dir.join("maps.obj"), obj).unwrap();
+    let mesh = TriangleMesh::from_obj(dir.join("maps.obj")).unwrap();
+    let ray = Ray::new(Point3::new(0.2, 0.2, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = mesh.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    let (_, leaf) = mesh.materials()[0].scatter(&ray, &rec).unwrap();
+    assert_eq!(leaf, Color::new(0.0, 1.0, 0.0));
+    let (_, photo) = mesh.materials()[1].scatter(&ray, &rec).unwrap();
+    assert_eq!(photo, Color::new(0.0, 0.0, 1.0));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use ray_tracing_utils::material::Lambertian;
+use ray_tracing_utils::hittable::{Hittable, Sphere};
+use ray_tracing_utils::math::{Color, Point3, Ray, Vec3};
+use ray_tracing_utils::perlin::Perlin;
+use ray_tracing_utils::texture::{MarbleTexture, NoiseTexture, Texture, TurbulenceTexture, WoodTexture};
+
+fn points() -> impl Iterator<Item = Point3> {
+    (0..500).map(|i| {
+        let t = i as f32;
+        Point3::new(t * 0.173 - 40.0, (t * 0.619).sin() * 13.0, t * 0.0371)
+    })
+}
+
+#[test]
+fn same_seed_same_noise() {
+    let a = Perlin::with_seed(7);
+    let b = Perlin::with_seed(7);
+    let c = Perlin::with_seed(8);
+    let mut differs = false;
+    for p in points() {
+        assert_eq!(a.noise(p), b.noise(p));
+        assert_eq!(a.fbm(p, 5, 2.0, 0.5), b.fbm(p, 5, 2.0, 0.5));
+        differs |= a.noise(p) != c.noise(p);
+    }
+    assert!(differs);
+}
+
+#[test]
+fn noise_range_and_lattice() {
+    let perlin = Perlin::with_seed(1);
+    for p in points() {
+        let n = perlin.noise(p);
+        assert!((-1.0..=1.0).contains(&n), "{} at {:?}", n, p);
+        let t = perlin.turbulence(p, 7);
+        assert!((0.0..2.0).contains(&t));
+    }
+    // Gradient noise vanishes on the integer lattice, including negative cells.
+    for p in [Point3::new(0.0, 0.0, 0.0), Point3::new(3.0, -5.0, 300.0)] {
+        assert!(perlin.noise(p).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn noise_is_continuous() {
+    let perlin = Perlin::with_seed(3);
+    let step = Vec3::new(1e-3, 1e-3, 1e-3);
+    for p in points() {
+        assert!((perlin.noise(p) - perlin.noise(p + step)).abs() < 0.01);
+    }
+}
+
+#[test]
+fn procedural_textures_stay_in_range() {
+    let textures: Vec<Arc<dyn Texture>> = vec![
+        Arc::new(NoiseTexture::new(0, 4.0)),
+        Arc::new(TurbulenceTexture::new(0, 4.0)),
+        Arc::new(MarbleTexture::new(0, 4.0)),
+        Arc::new(WoodTexture::new(0, 4.0)),
+    ];
+    for texture in &textures {
+        for p in points() {
+            let c = texture.value(0.0, 0.0, p);
+            for i in 0..3 {
+                assert!((0.0..=1.0).contains(&c[i]));
+            }
+        }
+    }
+}
+
+#[test]
+fn lambertian_uses_noise_texture() {
+    let texture = Arc::new(MarbleTexture::new(5, 4.0));
+    let sphere = Sphere {
+        center: Point3::new(0.0, 0.0, -1.0),
+        radius: 0.5,
+        material: Box::new(Lambertian::textured(texture.clone())),
+    };
+    let ray = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, -1.0));
+    let rec = sphere.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    let (_, attenuation) = rec.material.scatter(&ray, &rec).unwrap();
+    assert_eq!(attenuation, texture.value(rec.u, rec.v, rec.p));
+    assert_ne!(attenuation, Color::default());
+}
@@ -0,0 +1,155 @@
+use ray_tracing_utils::hittable::Hittable;
+use ray_tracing_utils::math::{Color, Point3, Ray, Vec3};
+use ray_tracing_utils::mesh::ply::{self, PlyError};
+use ray_tracing_utils::mesh::stl::{self, StlError};
+
+fn down(x: f32, y: f32) -> Ray {
+    Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0))
+}
+
+const ASCII_PLY: &str = "\
+ply
+format ascii 1.0
+comment unit quad with a red and a blue side
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+0 0 0 255 0 0
+1 0 0 255 0 0
+1 1 0 0 0 255
+0 1 0 0 0 255
+4 0 1 2 3
+0 2
+";
+
+#[test]
+fn ascii_ply_with_vertex_colors() {
+    let mesh = ply::read(ASCII_PLY.as_bytes()).unwrap();
+    assert_eq!(mesh.len(), 2);
+    assert_eq!(mesh.positions().len(), 4);
+    assert_eq!(mesh.colors()[0], Color::new(1.0, 0.0, 0.0));
+
+    let red = mesh.hit(&down(0.5, 0.0001), 0.001, f32::INFINITY).unwrap();
+    let blue = mesh.hit(&down(0.5, 0.9999), 0.001, f32::INFINITY).unwrap();
+    let middle = mesh.hit(&down(0.5, 0.5), 0.001, f32::INFINITY).unwrap();
+    let color = |rec: &ray_tracing_utils::hittable::HitRecord| rec.color.unwrap();
+    assert!(color(&red).x > 0.99 && color(&blue).z > 0.99);
+    assert!((color(&middle).x - 0.5).abs() < 1e-3 && (color(&middle).z - 0.5).abs() < 1e-3);
+
+    // The albedo of the default material follows the vertex colors.
+    let ray = down(0.5, 0.0001);
+    let (_, albedo) = red.material.scatter(&ray, &red).unwrap();
+    assert_eq!(albedo, color(&red));
+}
+
+fn binary_ply(big_endian: bool) -> Vec<u8> {
+    let format = if big_endian { "binary_big_endian" } else { "binary_little_endian" };
+    let mut data = format!(
+        "ply\nformat {} 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+         property float nx\nproperty float ny\nproperty float nz\nproperty float u\nproperty float v\n\
+         element face 1\nproperty list uchar uint vertex_indices\nend_header\n",
+        format,
+    ).into_bytes();
+    let f32s = |data: &mut Vec<u8>, values: &[f32]| {
+        for v in values {
+            data.extend(if big_endian { v.to_be_bytes() } else { v.to_le_bytes() });
+        }
+    };
+    f32s(&mut data, &[0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+    f32s(&mut data, &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+    f32s(&mut data, &[0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0]);
+    data.push(3);
+    for i in 0u32..3 {
+        data.extend(if big_endian { i.to_be_bytes() } else { i.to_le_bytes() });
+    }
+    data
+}
+
+#[test]
+fn binary_ply_both_byte_orders() {
+    for big_endian in [false, true] {
+        let mesh = ply::decode(&binary_ply(big_endian)).unwrap();
+        assert_eq!(mesh.len(), 1);
+        assert_eq!(mesh.positions()[1], Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(mesh.triangles()[0].normals, Some([0, 1, 2]));
+        assert!(mesh.colors().is_empty());
+        let rec = mesh.hit(&down(0.25, 0.5), 0.001, f32::INFINITY).unwrap();
+        assert!((rec.u - 0.25).abs() < 1e-5 && (rec.v - 0.5).abs() < 1e-5);
+        assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert!(rec.color.is_none());
+    }
+}
+
+#[test]
+fn ply_errors() {
+    assert!(matches!(ply::decode(b"obj\n"), Err(PlyError::BadMagic)));
+    let bad_type = "ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n";
+    assert!(matches!(ply::decode(bad_type.as_bytes()), Err(PlyError::Header { line: 4, .. })));
+    let truncated = binary_ply(false);
+    assert!(matches!(ply::decode(&truncated[..truncated.len() - 2]), Err(PlyError::UnexpectedEof)));
+    let out_of_range = ASCII_PLY.replace("4 0 1 2 3", "3 0 1 7");
+    assert!(matches!(ply::decode(out_of_range.as_bytes()), Err(PlyError::InvalidData(_))));
+}
+
+const ASCII_STL: &str = "\
+solid quad
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid quad
+";
+
+#[test]
+fn ascii_stl_welds_vertices() {
+    let mesh = stl::read(ASCII_STL.as_bytes()).unwrap();
+    assert_eq!(mesh.len(), 2);
+    assert_eq!(mesh.positions().len(), 4);
+    assert!(mesh.hit(&down(0.1, 0.9), 0.001, f32::INFINITY).unwrap().front_face);
+
+    let broken = ASCII_STL.replace("vertex 1 1 0\n    endloop", "vertex 1 one 0\n    endloop");
+    assert!(matches!(stl::read(broken.as_bytes()), Err(StlError::Parse { line: 6, .. })));
+    let short = ASCII_STL.replacen("      vertex 1 0 0\n", "", 1);
+    assert!(matches!(stl::read(short.as_bytes()), Err(StlError::Parse { line: 7, .. })));
+}
+
+#[test]
+fn binary_stl_starting_with_solid() {
+    let mut data = b"solid but actually binary".to_vec();
+    data.resize(80, 0);
+    data.extend(2u32.to_le_bytes());
+    for corners in [[[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]], [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, -0.0]]] {
+        data.extend([0u8; 12]);
+        for v in corners.iter().flatten() {
+            data.extend(v.to_le_bytes());
+        }
+        data.extend([0u8; 2]);
+    }
+    let mesh = stl::decode(&data).unwrap();
+    assert_eq!(mesh.len(), 2);
+    assert_eq!(mesh.positions().len(), 4);
+    assert!(mesh.hit(&down(0.9, 0.1), 0.001, f32::INFINITY).is_some());
+
+    assert!(matches!(stl::decode(&data[..data.len() - 1]), Err(StlError::UnrecognizedFormat)));
+    assert!(matches!(stl::decode(b"\x00\x01garbage"), Err(StlError::UnrecognizedFormat)));
+}
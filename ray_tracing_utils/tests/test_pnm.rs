@@ -0,0 +1,114 @@
+use ray_tracing_utils::image::{Image, Pixel};
+use ray_tracing_utils::pnm::{decode, PnmError, PpmEncoding};
+
+fn rgb(image: &Image) -> Vec<(u8, u8, u8)> {
+    image.data.iter().map(|px: &Pixel| (px.r, px.g, px.b)).collect()
+}
+
+#[test]
+fn p3_with_comments_and_free_layout() {
+    let data = b"P3 # a comment right after the magic\n\
+        # another comment\n\
+        3\t1\n  255 255 0 0 0 255\n0 # comment inside the raster\n 12 34 56";
+    let image = decode(data).unwrap();
+    assert_eq!((image.width, image.height), (3, 1));
+    assert_eq!(rgb(&image), vec![(255, 0, 0), (0, 255, 0), (12, 34, 56)]);
+    assert!(image.data.iter().all(|px| px.a == 255));
+}
+
+#[test]
+fn p3_with_small_maxval_is_rescaled() {
+    let image = decode(b"P3\n2 1\n15\n15 0 7 1 2 3\n").unwrap();
+    assert_eq!(rgb(&image), vec![(255, 0, 119), (17, 34, 51)]);
+}
+
+#[test]
+fn p1_and_p4_bitmaps() {
+    let ascii = decode(b"P1\n# bitmap\n5 2\n10101\n0 1 0 1 0\n").unwrap();
+    let binary = decode(b"P4\n5 2\n\xa8\x50").unwrap();
+    let expected = vec![0, 255, 0, 255, 0, 255, 0, 255, 0, 255];
+    for image in [ascii, binary] {
+        assert_eq!((image.width, image.height), (5, 2));
+        let values: Vec<u8> = image.data.iter().map(|px| px.r).collect();
+        assert_eq!(values, expected);
+    }
+}
+
+#[test]
+fn p2_and_p5_graymaps() {
+    let ascii = decode(b"P2 3 1 255 0 128 255").unwrap();
+    let binary = decode(b"P5 3 1 255\n\x00\x80\xff").unwrap();
+    for image in [ascii, binary] {
+        assert_eq!(rgb(&image), vec![(0, 0, 0), (128, 128, 128), (255, 255, 255)]);
+    }
+}
+
+#[test]
+fn p6_8_and_16_bit() {
+    let eight = decode(b"P6\n2 1\n255\n\x01\x02\x03\xfd\xfe\xff").unwrap();
+    assert_eq!(rgb(&eight), vec![(1, 2, 3), (253, 254, 255)]);
+
+    let sixteen = decode(b"P6 1 1 65535\n\xff\xff\x80\x00\x00\x00").unwrap();
+    assert_eq!(rgb(&sixteen), vec![(255, 128, 0)]);
+}
+
+#[test]
+fn binary_raster_may_start_with_whitespace_bytes() {
+    // The byte after maxval is the only separator; a raster value of 0x20 is data.
+    let image = decode(b"P5 2 1 255\n\x20\x0a").unwrap();
+    assert_eq!(rgb(&image), vec![(32, 32, 32), (10, 10, 10)]);
+}
+
+#[test]
+fn errors_are_reported() {
+    assert!(matches!(decode(b"P7\n1 1\n255\n"), Err(PnmError::BadMagic(_))));
+    assert!(matches!(decode(b"P3\n2 1\n255\n1 2 3\n"), Err(PnmError::UnexpectedEof)));
+    assert!(matches!(decode(b"P6\n2 1\n255\n\x00\x00"), Err(PnmError::UnexpectedEof)));
+    assert!(matches!(decode(b"P3\n1 x\n"), Err(PnmError::InvalidNumber { offset: 5, .. })));
+    assert!(matches!(
+        decode(b"P2\n1 1\n100\n101\n"),
+        Err(PnmError::SampleOutOfRange { value: 101, maxval: 100, .. })
+    ));
+    assert!(matches!(decode(b"P2\n0 1\n255\n"), Err(PnmError::InvalidHeader(_))));
+    assert!(matches!(decode(b"P2\n1 1\n70000\n0\n"), Err(PnmError::InvalidHeader(_))));
+    assert!(matches!(Image::from_path("/nonexistent/file.ppm"), Err(PnmError::Io(_))));
+}
+
+fn sample_image() -> Image {
+    let mut image = Image::new(2, 2);
+    image.data[0] = Pixel { r: 255, g: 0, b: 0, a: 255 };
+    image.data[1] = Pixel { r: 0, g: 10, b: 200, a: 0 };
+    image.data[3] = Pixel { r: 1, g: 2, b: 3, a: 255 };
+    image
+}
+
+#[test]
+fn write_p3() {
+    let mut out = vec![];
+    sample_image().write_ppm(&mut out, PpmEncoding::Ascii).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "P3\n2 2\n255\n255 0 0\n0 10 200\n0 0 0\n1 2 3\n"
+    );
+}
+
+#[test]
+fn write_p6() {
+    let mut out = vec![];
+    sample_image().write_ppm(&mut out, PpmEncoding::Binary).unwrap();
+    let mut expected = b"P6\n2 2\n255\n".to_vec();
+    expected.extend_from_slice(&[255, 0, 0, 0, 10, 200, 0, 0, 0, 1, 2, 3]);
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn written_ppm_reads_back() {
+    let image = sample_image();
+    for encoding in [PpmEncoding::Ascii, PpmEncoding::Binary] {
+        let mut out = vec![];
+        image.write_ppm(&mut out, encoding).unwrap();
+        let read = decode(&out).unwrap();
+        assert_eq!((read.width, read.height), (2, 2));
+        assert_eq!(rgb(&read), rgb(&image));
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use ray_tracing_utils::math::{Vec3, Point3, Color, Ray, Transform};
+use ray_tracing_utils::hittable::{Hittable, Plane, XyRect, XzRect, YzRect, Quad, Cuboid, Instance};
+use ray_tracing_utils::material::{Lambertian, Isotropic};
+use ray_tracing_utils::medium::ConstantMedium;
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn plane_is_flat_to_the_horizon() {
+    let plane = Plane::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Box::new(Lambertian::default()));
+    assert_eq!(plane.normal(), Vec3::new(0.0, 1.0, 0.0));
+    assert!(plane.bounding_box().is_none());
+
+    // A grazing ray far away still lands exactly on y = 1.
+    let ray = Ray::new(Point3::new(0.0, 2.0, 0.0), Vec3::new(1000.0, -0.001, 0.0));
+    let rec = plane.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!(close(rec.p.y, 1.0), "{:?}", rec.p);
+    assert!(rec.front_face);
+    assert_eq!(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+
+    // From below it is the back face, and parallel rays miss.
+    let up = Ray::new(Point3::new(3.0, 0.0, -2.0), Vec3::new(0.0, 1.0, 0.0));
+    let rec = plane.hit(&up, 0.001, f32::INFINITY).unwrap();
+    assert!(!rec.front_face);
+    assert_eq!(rec.normal, Vec3::new(0.0, -1.0, 0.0));
+    // Surface coordinates measure distance within the plane.
+    assert!(close(rec.u * rec.u + rec.v * rec.v, 13.0));
+    assert!(plane.hit(&Ray::new(Point3::default(), Vec3::new(1.0, 0.0, 0.0)), 0.001, f32::INFINITY).is_none());
+}
+
+#[test]
+fn axis_rects_face_their_axis_with_uvs_across_them() {
+    let material = || Box::new(Lambertian::default());
+    // Bounds in either order.
+    let xy = XyRect::new(0.0, 2.0, 4.0, 0.0, -1.0, material());
+    let xz = XzRect::new(2.0, 0.0, 0.0, 4.0, -1.0, material());
+    let yz = YzRect::new(0.0, 2.0, 0.0, 4.0, -1.0, material());
+    let cases: [(&dyn Hittable, Point3, Vec3); 3] = [
+        (&xy, Point3::new(0.5, 3.0, 5.0), Vec3::new(0.0, 0.0, -1.0)),
+        (&xz, Point3::new(0.5, 5.0, 3.0), Vec3::new(0.0, -1.0, 0.0)),
+        (&yz, Point3::new(5.0, 0.5, 3.0), Vec3::new(-1.0, 0.0, 0.0)),
+    ];
+    for (rect, origin, direction) in cases {
+        let rec = rect.hit(&Ray::new(origin, direction), 0.001, f32::INFINITY).unwrap();
+        assert!(close(rec.t, 6.0));
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, -direction);
+        assert!(close(rec.u, 0.25) && close(rec.v, 0.75), "{} {}", rec.u, rec.v);
+
+        let bbox = rect.bounding_box().unwrap();
+        assert!(bbox.extent().x > 0.0 && bbox.extent().y > 0.0 && bbox.extent().z > 0.0);
+        let outside = Ray::new(origin + Vec3::new(3.0, 3.0, 3.0) + direction * 3.0, direction);
+        assert!(rect.hit(&outside, 0.001, f32::INFINITY).is_none());
+    }
+}
+
+#[test]
+#[should_panic(expected = "XzRect has no area")]
+fn axis_rects_need_an_area() {
+    XzRect::new(1.0, 1.0, 0.0, 4.0, 0.0, Box::new(Lambertian::default()));
+}
+
+#[test]
+fn quad_maps_its_edges_to_uvs() {
+    // A slanted parallelogram.
+    let quad = Quad::new(
+        Point3::new(1.0, 0.0, 0.0),
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(1.0, 1.0, -1.0),
+        Box::new(Lambertian::default()),
+    );
+    let n = quad.normal();
+    assert!(close(n.length(), 1.0));
+
+    let target = Point3::new(1.0, 0.0, 0.0) + Vec3::new(2.0, 0.0, 0.0) * 0.3 + Vec3::new(1.0, 1.0, -1.0) * 0.6;
+    let origin = target + n * 2.0;
+    let rec = quad.hit(&Ray::new(origin, target - origin), 0.001, f32::INFINITY).unwrap();
+    assert!(close(rec.u, 0.3) && close(rec.v, 0.6), "{} {}", rec.u, rec.v);
+    assert!(rec.front_face);
+    assert!((rec.p - target).length() < 1e-4);
+
+    // Just past the far edge along `v`.
+    let beyond = Point3::new(1.0, 0.0, 0.0) + Vec3::new(1.0, 1.0, -1.0) * 1.01;
+    let origin = beyond + n;
+    assert!(quad.hit(&Ray::new(origin, beyond - origin), 0.001, f32::INFINITY).is_none());
+}
+
+#[test]
+fn cuboid_sides_face_outward() {
+    let cuboid = Cuboid::new(Point3::new(1.0, 2.0, 3.0), Point3::new(-1.0, 0.0, 0.0), Box::new(Lambertian::default()));
+    // Padded like its flat sides.
+    let bbox = cuboid.bounding_box().unwrap();
+    assert!((bbox.min - Point3::new(-1.0, 0.0, 0.0)).length() < 1e-3 && bbox.min.x < -1.0);
+    assert!((bbox.max - Point3::new(1.0, 2.0, 3.0)).length() < 1e-3 && bbox.max.z > 3.0);
+
+    let center = Point3::new(0.0, 1.0, 1.5);
+    for side in cuboid.sides() {
+        let (u, v) = side.edges();
+        let middle = side.corner() + (u + v) * 0.5;
+        assert!(Vec3::dot(side.normal(), middle - center) > 0.0);
+    }
+
+    let axes = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)];
+    for axis in axes {
+        for direction in [axis, -axis] {
+            // From outside the box toward its center, and from the center out.
+            let ray = Ray::new(center + direction * 10.0, -direction);
+            let rec = cuboid.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            assert!(rec.front_face);
+            assert_eq!(rec.normal, direction);
+            assert!((0.0..=1.0).contains(&rec.u) && (0.0..=1.0).contains(&rec.v));
+
+            let rec = cuboid.hit(&Ray::new(center, direction), 0.001, f32::INFINITY).unwrap();
+            assert!(!rec.front_face);
+            assert_eq!(rec.normal, -direction);
+        }
+    }
+}
+
+#[test]
+fn rotated_cuboid_bounds_smoke() {
+    let cuboid = Cuboid::new(Point3::default(), Point3::new(2.0, 2.0, 2.0), Box::new(Lambertian::default()));
+    let transform = Transform::translate(Vec3::new(5.0, 0.0, 0.0)) * Transform::rotate_y(45f32.to_radians());
+    let block = Instance::new(Arc::new(cuboid), transform);
+    let smoke = ConstantMedium::new(Box::new(block), 0.5, Box::new(Isotropic::new(Color::new(1.0, 1.0, 1.0))));
+
+    // Straight through the diagonal of the turned block, 2 * sqrt(2) long.
+    let ray = Ray::new(Point3::new(5.0 + 2f32.sqrt(), 1.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+    let expected = (-0.5 * 2.0 * 2f32.sqrt()).exp();
+    assert!(close(smoke.transmittance(&ray, 0.001, f32::INFINITY), expected));
+}
@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use ray_tracing_utils::camera::Camera;
+use ray_tracing_utils::environment::{Environment, HdrEnvironment, ImageEnvironment, UniformEnvironment};
+use ray_tracing_utils::framebuffer::ResolveSettings;
+use ray_tracing_utils::hittable::{HittableList, Sphere};
+use ray_tracing_utils::material::{DiffuseLight, Lambertian};
+use ray_tracing_utils::math::{Color, Point3, Ray, Vec3};
+use ray_tracing_utils::render::{RenderSettings, Renderer};
+
+fn camera(settings: &RenderSettings) -> Camera {
+    Camera::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        std::f32::consts::FRAC_PI_2,
+        settings.aspect_ratio(),
+        0.0,
+        1.0,
+    )
+}
+
+#[test]
+fn empty_world_shows_solid_background() {
+    let settings = RenderSettings {
+        width: 20,
+        height: 10,
+        samples_per_pixel: 4,
+        max_depth: 5,
+        environment: Arc::new(UniformEnvironment { color: Color::new(0.25, 1.0, 0.0) }),
+    };
+    let renderer = Renderer::new(settings.clone());
+    let image = renderer
+        .render(&camera(&settings), &HittableList::default())
+        .resolve(&ResolveSettings::default());
+
+    assert_eq!((image.width, image.height), (20, 10));
+    assert_eq!(image.data.len(), 200);
+    for px in image.data.iter() {
+        assert_eq!((px.r, px.g, px.b, px.a), (128, 255, 0, 255));
+    }
+}
+
+#[test]
+fn sky_is_bluer_at_the_top() {
+    let settings = RenderSettings {
+        width: 8,
+        height: 8,
+        samples_per_pixel: 1,
+        ..Default::default()
+    };
+    let renderer = Renderer::new(settings.clone());
+    let image = renderer
+        .render(&camera(&settings), &HittableList::default())
+        .resolve(&ResolveSettings::default());
+
+    let top = image.get_pixel(0);
+    let bottom = image.get_pixel(7 * 8);
+    assert!(top.r < bottom.r);
+    assert_eq!(top.b, bottom.b);
+}
+
+#[test]
+fn accumulate_adds_samples() {
+    let settings = RenderSettings {
+        width: 4,
+        height: 3,
+        samples_per_pixel: 2,
+        max_depth: 5,
+        environment: Arc::new(UniformEnvironment { color: Color::new(0.5, 0.5, 0.5) }),
+    };
+    let renderer = Renderer::new(settings.clone());
+    let camera = camera(&settings);
+    let world = HittableList::default();
+
+    let mut framebuffer = renderer.render(&camera, &world);
+    renderer.accumulate(&camera, &world, &mut framebuffer, |_| ());
+
+    assert_eq!(framebuffer.count(3, 2), 4);
+    assert_eq!(framebuffer.sum(3, 2), Color::new(2.0, 2.0, 2.0));
+    assert_eq!(framebuffer.get(0, 0), Color::new(0.5, 0.5, 0.5));
+}
+
+#[test]
+fn seeded_renders_repeat_regardless_of_threads() {
+    let settings = RenderSettings {
+        width: 12,
+        height: 8,
+        samples_per_pixel: 2,
+        max_depth: 4,
+        ..Default::default()
+    };
+    let camera = camera(&settings);
+    let world = HittableList {
+        hittables: vec![Box::new(Sphere {
+            center: Point3::new(0.0, 0.0, -2.0),
+            radius: 1.0,
+            material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        })],
+    };
+    let render = |seed: u64, threads: usize| {
+        let mut renderer = Renderer::new(settings.clone());
+        renderer.seed = Some(seed);
+        renderer.tiles.threads = threads;
+        let mut framebuffer = renderer.render(&camera, &world);
+        let first = framebuffer.sum(6, 4);
+        renderer.accumulate(&camera, &world, &mut framebuffer, |_| ());
+        // A second pass draws fresh samples rather than repeating the first.
+        assert_ne!(framebuffer.sum(6, 4), first * 2.0);
+        (0..8).flat_map(|y| (0..12).map(move |x| (x, y))).map(|(x, y)| framebuffer.sum(x, y)).collect::<Vec<_>>()
+    };
+
+    assert_eq!(render(3, 1), render(3, 2));
+    assert_ne!(render(3, 1), render(4, 1));
+}
+
+#[test]
+fn lights_are_visible_against_black_background() {
+    let settings = RenderSettings {
+        width: 4,
+        height: 4,
+        samples_per_pixel: 2,
+        max_depth: 5,
+        environment: Arc::new(UniformEnvironment { color: Color::new(0.0, 0.0, 0.0) }),
+    };
+    let mut world = HittableList::default();
+    world.hittables.push(Box::new(Sphere {
+        center: Point3::new(0.0, 0.0, -2.0),
+        radius: 100.0,
+        material: Box::new(DiffuseLight { emit: Color::new(0.25, 4.0, 0.0) }),
+    }));
+
+    let framebuffer = Renderer::new(settings.clone()).render(&camera(&settings), &world);
+    assert!(framebuffer.colors().all(|c| c == Color::new(0.25, 4.0, 0.0)));
+}
+
+#[test]
+fn unlit_scene_is_black() {
+    let settings = RenderSettings {
+        width: 4,
+        height: 4,
+        samples_per_pixel: 2,
+        max_depth: 5,
+        environment: Arc::new(UniformEnvironment { color: Color::new(0.0, 0.0, 0.0) }),
+    };
+    let mut world = HittableList::default();
+    world.hittables.push(Box::new(Sphere {
+        center: Point3::new(0.0, 0.0, -2.0),
+        radius: 1.0,
+        material: Box::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+    }));
+
+    let framebuffer = Renderer::new(settings.clone()).render(&camera(&settings), &world);
+    assert!(framebuffer.colors().all(|c| c == Color::new(0.0, 0.0, 0.0)));
+}
+
+fn sun_map() -> ImageEnvironment {
+    let (width, height) = (64, 32);
+    let mut texels = vec![Color::new(0.1, 0.2, 0.3); width * height];
+    texels[8 * width + 20] = Color::new(5000.0, 5000.0, 5000.0);
+    ImageEnvironment::new(width as u32, height as u32, texels)
+}
+
+/// Radiance leaving an upward-facing Lambertian point under `map`, computed
+/// exactly from the texels of the upper hemisphere.
+fn expected_radiance(map: &ImageEnvironment, albedo: f32) -> Color {
+    let (width, height) = (map.width, map.height);
+    let d_phi = 2.0 * std::f32::consts::PI / width as f32;
+    let mut irradiance = Color::default();
+    for y in 0..height / 2 {
+        let theta0 = y as f32 / height as f32 * std::f32::consts::PI;
+        let theta1 = (y + 1) as f32 / height as f32 * std::f32::consts::PI;
+        let weight = d_phi * (theta1.sin().powi(2) - theta0.sin().powi(2)) / 2.0;
+        for x in 0..width {
+            irradiance = irradiance + map.texel(x, y) * weight;
+        }
+    }
+    irradiance * (albedo / std::f32::consts::PI)
+}
+
+/// Mean and variance of the red channel seen straight down onto a sphere.
+fn estimate(environment: Arc<dyn Environment>, n: usize) -> (f32, f32) {
+    let settings = RenderSettings { max_depth: 4, environment, ..Default::default() };
+    let renderer = Renderer::new(settings);
+    let mut world = HittableList::default();
+    world.hittables.push(Box::new(Sphere {
+        center: Point3::new(0.0, 0.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    }));
+
+    let ray = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    let samples: Vec<f32> = (0..n).map(|_| renderer.ray_color(&ray, &world, 4).x).collect();
+    let mean = samples.iter().sum::<f32>() / n as f32;
+    let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / n as f32;
+    (mean, variance)
+}
+
+#[test]
+fn importance_sampled_environment_is_unbiased_and_less_noisy() {
+    let expected = expected_radiance(&sun_map(), 0.5).x;
+
+    let (mis, mis_variance) = estimate(Arc::new(HdrEnvironment::new(sun_map())), 20000);
+    let (plain, plain_variance) = estimate(Arc::new(sun_map()), 20000);
+
+    assert!((mis - expected).abs() < 0.02 * expected, "mis {} expected {}", mis, expected);
+    assert!((plain - expected).abs() < 0.5 * expected, "plain {} expected {}", plain, expected);
+    assert!(mis_variance * 100.0 < plain_variance, "{} vs {}", mis_variance, plain_variance);
+}
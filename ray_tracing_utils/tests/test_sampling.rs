@@ -0,0 +1,72 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use ray_tracing_utils::sampling::{Distribution1D, Distribution2D};
+
+#[test]
+fn distribution_1d_follows_function() {
+    let dist = Distribution1D::new(&[1.0, 0.0, 3.0, 0.0]);
+    assert_eq!(dist.integral(), 1.0);
+    assert_eq!(dist.pdf(0.1), 1.0);
+    assert_eq!(dist.pdf(0.3), 0.0);
+    assert_eq!(dist.pdf(0.6), 3.0);
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut counts = [0; 4];
+    for _ in 0..40000 {
+        let (x, pdf, index) = dist.sample(rng.gen());
+        assert!((0.0..1.0).contains(&x));
+        assert_eq!(index, (x * 4.0) as usize);
+        assert_eq!(pdf, dist.pdf(x));
+        counts[index] += 1;
+    }
+    assert_eq!(counts[1] + counts[3], 0);
+    let ratio = counts[2] as f32 / counts[0] as f32;
+    assert!((ratio - 3.0).abs() < 0.15, "ratio {}", ratio);
+}
+
+#[test]
+fn distribution_1d_edges() {
+    let dist = Distribution1D::new(&[0.0, 2.0]);
+    assert_eq!(dist.sample(0.0).2, 1);
+    assert_eq!(dist.sample(0.5).0, 0.75);
+    assert!(dist.sample(0.999_999).0 < 1.0);
+
+    let zero = Distribution1D::new(&[0.0, 0.0]);
+    assert_eq!(zero.integral(), 0.0);
+    assert_eq!(zero.pdf(0.7), 1.0);
+    assert_eq!(zero.sample(0.25).0, 0.25);
+}
+
+#[test]
+fn distribution_2d_pdf_integrates_to_one() {
+    let (width, height) = (5, 4);
+    let func: Vec<f32> = (0..width * height).map(|i| ((i * 7) % 5) as f32).collect();
+    let dist = Distribution2D::new(&func, width, height);
+
+    let mut integral = 0.0;
+    for y in 0..height {
+        for x in 0..width {
+            let (u, v) = ((x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32);
+            integral += dist.pdf(u, v) / (width * height) as f32;
+        }
+    }
+    assert!((integral - 1.0).abs() < 1e-5);
+
+    let mut rng = StdRng::seed_from_u64(8);
+    for _ in 0..1000 {
+        let ((u, v), pdf) = dist.sample(rng.gen(), rng.gen());
+        assert!(pdf > 0.0);
+        assert!((pdf - dist.pdf(u, v)).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn distribution_2d_skips_empty_rows() {
+    let dist = Distribution2D::new(&[0.0, 0.0, 1.0, 1.0], 2, 2);
+    let mut rng = StdRng::seed_from_u64(9);
+    for _ in 0..100 {
+        let ((_, v), _) = dist.sample(rng.gen(), rng.gen());
+        assert!(v >= 0.5);
+    }
+    assert_eq!(dist.pdf(0.5, 0.25), 0.0);
+}
@@ -0,0 +1,317 @@
+use std::fs;
+use std::path::Path;
+
+use ray_tracing_utils::math::{Color, Point3, Ray, Vec3};
+use ray_tracing_utils::aabb::Aabb;
+use ray_tracing_utils::image::{Image, Pixel};
+use ray_tracing_utils::voxel::{self, VoxelGrid};
+use ray_tracing_utils::scene::file::{self, SceneError};
+use ray_tracing_utils::scene::Scene;
+
+const SCENE: &str = r#"
+[camera]
+lookfrom = [0, 0, 5]
+lookat = [0, 0, 0]
+vfov = 40
+aperture = 0.0
+
+[render]
+width = 200
+height = 100
+samples_per_pixel = 8
+max_depth = 5
+environment = { type = "uniform", color = [0.1, 0.2, 0.3] }
+
+[materials.red]
+type = "lambertian"
+albedo = [1, 0, 0]
+
+[materials.checker]
+type = "lambertian"
+texture = { type = "checker", even = [1, 1, 1], odd = [0, 0, 0], size = 0.5 }
+
+[materials.chrome]
+type = "metal"
+albedo = [0.9, 0.9, 0.9]
+fuzz = 0.1
+
+[[objects]]
+type = "sphere"
+center = [0, 0, 0]
+radius = 1
+material = "red"
+
+[[objects]]
+type = "triangle"
+vertices = [[-10, -2, -10], [10, -2, -10], [0, -2, 10]]
+material = "checker"
+
+[[lights]]
+type = "sphere"
+center = [0, 10, 0]
+radius = 2
+emit = [4, 4, 4]
+"#;
+
+fn load(text: &str) -> Scene {
+    file::from_toml(text, Path::new(".")).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn invalid_key(text: &str) -> (String, String) {
+    match file::from_toml(text, Path::new(".")) {
+        Err(SceneError::Invalid { key, message }) => (key, message),
+        Err(e) => panic!("unexpected error {}", e),
+        Ok(_) => panic!("scene should not load"),
+    }
+}
+
+#[test]
+fn toml_scene_builds_world_and_camera() {
+    let scene = load(SCENE);
+    assert_eq!((scene.settings.width, scene.settings.height), (200, 100));
+    assert_eq!((scene.settings.samples_per_pixel, scene.settings.max_depth), (8, 5));
+    assert_eq!(scene.settings.environment.color(Vec3::new(0.0, 1.0, 0.0)), Color::new(0.1, 0.2, 0.3));
+
+    let ray = scene.camera.get_ray(0.5, 0.5);
+    assert_eq!(ray.origin, Point3::new(0.0, 0.0, 5.0));
+    let rec = scene.world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!((rec.p.z - 1.0).abs() < 1e-4);
+    let (_, albedo) = rec.material.scatter(&ray, &rec).unwrap();
+    assert_eq!(albedo, Color::new(1.0, 0.0, 0.0));
+
+    let down = Ray::new(Point3::new(0.2, 0.0, 2.0), Vec3::new(0.0, -1.0, 0.0));
+    let rec = scene.world.hit(&down, 0.001, f32::INFINITY).unwrap();
+    assert!((rec.p.y + 2.0).abs() < 1e-5);
+
+    let up = Ray::new(Point3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let rec = scene.world.hit(&up, 0.001, f32::INFINITY).unwrap();
+    assert_eq!(rec.material.emitted(&up, &rec), Color::new(4.0, 4.0, 4.0));
+}
+
+#[test]
+fn json_uses_the_same_layout() {
+    let json = r#"{
+        "camera": {"lookfrom": [0, 0, 5], "lookat": [0, 0, 0], "vfov": 40},
+        "materials": {"glass": {"type": "dielectric", "ior": 1.5}},
+        "objects": [
+            {"type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "glass"},
+            {"type": "sphere", "center": [0, 0, 0], "radius": -0.9, "material": "glass"}
+        ]
+    }"#;
+    let scene = file::from_json(json, Path::new(".")).unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!(scene.settings.width, 384);
+    let ray = scene.camera.get_ray(0.5, 0.5);
+    assert!(scene.world.hit(&ray, 0.001, f32::INFINITY).is_some());
+    // The negative radius makes a hollow: its surface faces inward.
+    let inside = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert!(scene.world.hit(&inside, 0.001, f32::INFINITY).unwrap().front_face);
+}
+
+#[test]
+fn errors_point_at_the_key() {
+    let (key, message) = invalid_key(&SCENE.replace("radius = 1\n", "radios = 1\n"));
+    assert_eq!(key, "objects[0].radios");
+    assert!(message.starts_with("unknown key"), "{}", message);
+
+    assert_eq!(invalid_key(&SCENE.replace("lookat = [0, 0, 0]\n", "")).0, "camera.lookat");
+
+    // Keys that belong to another type are not silently ignored.
+    let (key, message) = invalid_key(&SCENE.replace("radius = 1\n", "radius = 1\nmin = [0, 0, 0]\n"));
+    assert_eq!(key, "objects[0].min");
+    assert!(message.starts_with("unknown key"), "{}", message);
+    assert_eq!(invalid_key(&SCENE.replace("radius = 2", "radius = 2\nvertices = []")).0, "lights[0].vertices");
+    assert_eq!(invalid_key(&SCENE.replace("fuzz = 0.1", "fuzz = 0.1\nior = 1.5")).0, "materials.chrome.ior");
+    assert_eq!(invalid_key(&SCENE.replace("size = 0.5", "size = 0.5, seed = 3")).0, "materials.checker.texture.seed");
+    assert_eq!(invalid_key(&SCENE.replace("color = [0.1, 0.2, 0.3]", "color = [0.1, 0.2, 0.3], top = [1, 1, 1]")).0,
+               "render.environment.top");
+    assert_eq!(invalid_key(&SCENE.replace("width = 200", "width = \"wide\"")).0, "render.width");
+    assert_eq!(invalid_key(&SCENE.replace("width = 200", "width = -5")).0, "render.width");
+    assert_eq!(invalid_key(&SCENE.replace("width = 200", "width = 0")),
+               ("render.width".to_string(), "must be greater than 0".to_string()));
+    assert_eq!(invalid_key(&SCENE.replace("samples_per_pixel = 8", "samples_per_pixel = 0")).0,
+               "render.samples_per_pixel");
+    let (key, message) = invalid_key(&SCENE.replace("lookat = [0, 0, 0]\n", "lookat = [0, 0, 0]\nvup = [0, 0, -2]\n"));
+    assert_eq!(key, "camera.vup");
+    assert!(message.contains("parallel"), "{}", message);
+    assert_eq!(invalid_key(&SCENE.replace("lookfrom = [0, 0, 5]", "lookfrom = [0, 5, 0]")).0, "camera.lookat");
+    assert_eq!(invalid_key(&SCENE.replace("material = \"red\"", "material = \"blue\"")).0, "objects[0].material");
+    assert_eq!(invalid_key(&SCENE.replace("albedo = [1, 0, 0]", "albedo = [1, 0]")).0, "materials.red.albedo");
+    assert_eq!(invalid_key(&SCENE.replace("vertices = [[-10, -2, -10], ", "vertices = [[-10, -2, \"x\"], ")).0,
+               "objects[1].vertices[0][2]");
+    assert_eq!(invalid_key(&SCENE.replace("type = \"checker\"", "type = \"plaid\"")).0, "materials.checker.texture.type");
+    assert_eq!(invalid_key(&SCENE.replace("radius = 2", "radius = 0")).0, "lights[0].radius");
+    assert_eq!(invalid_key(&SCENE.replace("radius = 1\n", "radius = 0\n")).0, "objects[0].radius");
+    assert_eq!(invalid_key(&SCENE.replace("vfov = 40", "vfov = 200")).0, "camera.vfov");
+    assert_eq!(invalid_key("[camera]\nlookfrom = [0, 0, 1]\nlookat = [0, 0, 0]\nvfov = 40\n[extra]\n").0, "extra");
+
+    let err = file::from_toml("[camera", Path::new(".")).err().unwrap();
+    assert!(matches!(err, SceneError::Syntax(_)));
+}
+
+#[test]
+fn meshes_load_relative_to_the_scene() {
+    let dir = std::env::temp_dir().join(format!("ray_tracing_scene_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("tri.obj"), "v -1 -1 0\nv 1 -1 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+    let text = r#"
+        [camera]
+        lookfrom = [0, 0, 5]
+        lookat = [0, 0, 0]
+        vfov = 40
+
+        [materials.chrome]
+        type = "metal"
+        albedo = [0.5, 0.5, 0.5]
+
+        [[objects]]
+        type = "mesh"
+        path = "tri.obj"
+        material = "chrome"
+    "#;
+    fs::write(dir.join("scene.toml"), text).unwrap();
+    let scene = file::load(dir.join("scene.toml")).unwrap_or_else(|e| panic!("{}", e));
+    let ray = scene.camera.get_ray(0.5, 0.5);
+    let rec = scene.world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    let (scattered, albedo) = rec.material.scatter(&ray, &rec).unwrap();
+    assert_eq!(albedo, Color::new(0.5, 0.5, 0.5));
+    assert_eq!(scattered.direction.normalized(), Vec3::new(0.0, 0.0, 1.0));
+
+    fs::write(dir.join("broken.toml"), text.replace("tri.obj", "missing.obj")).unwrap();
+    let err = file::load(dir.join("broken.toml")).err().unwrap();
+    assert!(matches!(&err, SceneError::Invalid { key, .. } if key == "objects[0].path"), "{}", err);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn planes_quads_and_boxes() {
+    let scene = load(r#"
+        [camera]
+        lookfrom = [0, 1, 5]
+        lookat = [0, 1, 0]
+        vfov = 40
+
+        [materials.grey]
+        type = "lambertian"
+        albedo = [0.5, 0.5, 0.5]
+
+        [[objects]]
+        type = "plane"
+        point = [0, 0, 0]
+        normal = [0, 1, 0]
+        material = "grey"
+
+        [[objects]]
+        type = "box"
+        min = [-0.5, 0, -0.5]
+        max = [0.5, 1, 0.5]
+        material = "grey"
+
+        [[lights]]
+        type = "quad"
+        corner = [-1, 3, -1]
+        u = [2, 0, 0]
+        v = [0, 0, 2]
+        emit = [4, 4, 4]
+    "#);
+    // The plane has no bounds, so neither does the world.
+    assert!(scene.world.bounding_box().is_none());
+
+    let down = Ray::new(Point3::new(0.0, 2.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    assert!((scene.world.hit(&down, 0.001, f32::INFINITY).unwrap().p.y - 1.0).abs() < 1e-4);
+    let beside = Ray::new(Point3::new(2.0, 2.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+    assert!(scene.world.hit(&beside, 0.001, f32::INFINITY).unwrap().p.y.abs() < 1e-4);
+    let up = Ray::new(Point3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let rec = scene.world.hit(&up, 0.001, f32::INFINITY).unwrap();
+    // u x v points down, so the light faces the scene.
+    assert_eq!(rec.material.emitted(&up, &rec), Color::new(4.0, 4.0, 4.0));
+
+    let (key, message) = invalid_key(r#"
+        [camera]
+        lookfrom = [0, 0, 5]
+        lookat = [0, 0, 0]
+        vfov = 40
+
+        [[lights]]
+        type = "quad"
+        corner = [0, 0, 0]
+        u = [1, 0, 0]
+        v = [2, 0, 0]
+        emit = [1, 1, 1]
+    "#);
+    assert_eq!(key, "lights[0].v");
+    assert!(message.contains("parallel"), "{}", message);
+}
+
+#[test]
+fn volumes_load_relative_to_the_scene() {
+    let dir = std::env::temp_dir().join(format!("ray_tracing_volume_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let bounds = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+    voxel::save(&VoxelGrid::new([1, 1, 1], bounds, vec![1.0]), dir.join("smoke.vol")).unwrap();
+    let text = r#"
+        [camera]
+        lookfrom = [0, 0, 5]
+        lookat = [0, 0, 0]
+        vfov = 40
+
+        [[objects]]
+        type = "volume"
+        path = "smoke.vol"
+        density = 1000
+        albedo = [0.5, 0.5, 0.5]
+        emit = [1, 2, 3]
+        anisotropy = 0.5
+    "#;
+    fs::write(dir.join("scene.toml"), text).unwrap();
+    let scene = file::load(dir.join("scene.toml")).unwrap_or_else(|e| panic!("{}", e));
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = scene.world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!((rec.p.z - 1.0).abs() < 0.05, "{:?}", rec.p);
+    assert_eq!(rec.material.emitted(&ray, &rec), Color::new(0.5, 1.0, 1.5));
+
+    fs::write(dir.join("bad.toml"), text.replace("anisotropy = 0.5", "anisotropy = 1.5")).unwrap();
+    let err = file::load(dir.join("bad.toml")).err().unwrap();
+    assert!(matches!(&err, SceneError::Invalid { key, .. } if key == "objects[0].anisotropy"), "{}", err);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn png_textures_load() {
+    let dir = std::env::temp_dir().join(format!("ray_tracing_png_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut image = Image::new(1, 1);
+    image.data[0] = Pixel { r: 255, g: 0, b: 0, a: 255 };
+    image.save_png(dir.join("red.png")).unwrap();
+    let text = r#"
+        [camera]
+        lookfrom = [0, 0, 5]
+        lookat = [0, 0, 0]
+        vfov = 40
+
+        [materials.painted]
+        type = "lambertian"
+        texture = { type = "image", path = "red.png" }
+
+        [[objects]]
+        type = "sphere"
+        center = [0, 0, 0]
+        radius = 1
+        material = "painted"
+    "#;
+    fs::write(dir.join("scene.toml"), text).unwrap();
+    let scene = file::load(dir.join("scene.toml")).unwrap_or_else(|e| panic!("{}", e));
+    fs::remove_dir_all(dir).unwrap();
+
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = scene.world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    let (_, albedo) = rec.material.scatter(&ray, &rec).unwrap();
+    assert_eq!(albedo, Color::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn bundled_example_loads() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../scenes/three_spheres.toml");
+    let scene = file::load(path).unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!((scene.settings.width, scene.settings.height), (400, 225));
+    assert!(scene.world.bounding_box().is_some());
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use ray_tracing_utils::hittable::{Hittable, Sphere};
+use ray_tracing_utils::image::{Image, Pixel};
+use ray_tracing_utils::material::Lambertian;
+use ray_tracing_utils::math::{Color, Point3, Ray, Vec3};
+use ray_tracing_utils::texture::{CheckerTexture, ImageTexture, SolidColor, Texture};
+
+#[test]
+fn solid_and_checker() {
+    let solid = SolidColor::new(Color::new(0.1, 0.2, 0.3));
+    assert_eq!(solid.value(0.7, 0.1, Point3::new(5.0, 5.0, 5.0)), Color::new(0.1, 0.2, 0.3));
+
+    let white = Color::new(1.0, 1.0, 1.0);
+    let black = Color::new(0.0, 0.0, 0.0);
+    let checker = CheckerTexture::new(white, black, 0.5);
+    assert_eq!(checker.value(0.0, 0.0, Point3::new(0.1, 0.1, 0.1)), white);
+    assert_eq!(checker.value(0.0, 0.0, Point3::new(0.6, 0.1, 0.1)), black);
+    assert_eq!(checker.value(0.0, 0.0, Point3::new(0.6, 0.6, 0.1)), white);
+    assert_eq!(checker.value(0.0, 0.0, Point3::new(-0.1, 0.1, 0.1)), black);
+    assert_eq!(checker.value(0.0, 0.0, Point3::new(-0.1, -0.1, -0.1)), black);
+}
+
+#[test]
+fn image_texture_lookup() {
+    let mut image = Image::new(2, 2);
+    image.data[0] = Pixel { r: 255, g: 0, b: 0, a: 255 };
+    image.data[3] = Pixel { r: 0, g: 0, b: 255, a: 255 };
+    let texture = ImageTexture::from_image(&image);
+    let p = Point3::default();
+
+    // v = 1 is the top row of the image.
+    assert_eq!(texture.value(0.0, 1.0, p), Color::new(1.0, 0.0, 0.0));
+    assert_eq!(texture.value(0.9, 0.1, p), Color::new(0.0, 0.0, 1.0));
+    assert_eq!(texture.value(0.9, 0.9, p), Color::new(0.0, 0.0, 0.0));
+    assert_eq!(texture.value(-3.0, 7.0, p), Color::new(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn sphere_hit_carries_uv() {
+    assert_eq!(Sphere::uv(Point3::new(1.0, 0.0, 0.0)), (0.5, 0.5));
+    assert_eq!(Sphere::uv(Point3::new(0.0, 1.0, 0.0)).1, 1.0);
+    assert_eq!(Sphere::uv(Point3::new(0.0, -1.0, 0.0)).1, 0.0);
+    assert_eq!(Sphere::uv(Point3::new(0.0, 0.0, 1.0)), (0.25, 0.5));
+
+    let sphere = Sphere {
+        center: Point3::new(0.0, 0.0, -5.0),
+        radius: 2.0,
+        material: Box::new(Lambertian::default()),
+    };
+    let ray = Ray::new(Point3::new(10.0, 0.0, -5.0), Vec3::new(-1.0, 0.0, 0.0));
+    let rec = sphere.hit(&ray, 0.0, f32::INFINITY).unwrap();
+    assert_eq!((rec.u, rec.v), (0.5, 0.5));
+}
+
+#[test]
+fn lambertian_uses_texture() {
+    let checker = Arc::new(CheckerTexture::new(Color::new(1.0, 0.0, 0.0), Color::new(0.0, 1.0, 0.0), 1.0));
+    let sphere = Sphere {
+        center: Point3::new(0.0, 0.0, 0.0),
+        radius: 1.5,
+        material: Box::new(Lambertian::textured(checker)),
+    };
+
+    let hit_at = |y: f32| {
+        let ray = Ray::new(Point3::new(5.0, y, 0.5), Vec3::new(-1.0, 0.0, 0.0));
+        let rec = sphere.hit(&ray, 0.0, f32::INFINITY).unwrap();
+        rec.material.scatter(&ray, &rec).unwrap().1
+    };
+    assert_eq!(hit_at(0.5), Color::new(0.0, 1.0, 0.0));
+    assert_eq!(hit_at(-0.5), Color::new(1.0, 0.0, 0.0));
+}
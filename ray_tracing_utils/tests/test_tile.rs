@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use ray_tracing_utils::math::Color;
+use ray_tracing_utils::tile::{Tile, TileRenderer};
+
+#[test]
+fn tiles_cover_frame_exactly_once() {
+    let renderer = TileRenderer::new(16, 4);
+    let tiles = renderer.tiles(50, 37);
+
+    let mut covered = vec![0; 50 * 37];
+    for tile in &tiles {
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                covered[(y * 50 + x) as usize] += 1;
+            }
+        }
+    }
+    assert!(covered.iter().all(|&c| c == 1));
+    assert_eq!(tiles[0], Tile { x: 0, y: 0, width: 16, height: 16 });
+    assert_eq!(*tiles.last().unwrap(), Tile { x: 48, y: 32, width: 2, height: 5 });
+}
+
+#[test]
+fn framebuffer_is_in_scanline_order() {
+    let (width, height) = (53, 29);
+    for threads in [1, 3, 8] {
+        let renderer = TileRenderer::new(8, threads);
+        let framebuffer = renderer.render(width, height, |x, y| Color::new(x as f32, y as f32, 0.0));
+
+        assert_eq!(framebuffer.len(), (width * height) as usize);
+        for (k, color) in framebuffer.iter().enumerate() {
+            let (x, y) = (k as u32 % width, k as u32 / width);
+            assert_eq!(*color, Color::new(x as f32, y as f32, 0.0));
+        }
+    }
+}
+
+#[test]
+fn progress_reports_every_pixel() {
+    let renderer = TileRenderer::new(7, 4);
+    let done = AtomicU32::new(0);
+    renderer.render_with_progress(30, 20, |_, _| Color::default(), |tile| {
+        done.fetch_add(tile.width * tile.height, Ordering::Relaxed);
+    });
+    assert_eq!(done.into_inner(), 30 * 20);
+}
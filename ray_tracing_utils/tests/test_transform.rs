@@ -0,0 +1,106 @@
+use std::f32::consts::FRAC_PI_2;
+use std::sync::Arc;
+
+use ray_tracing_utils::math::{Vec3, Point3, Ray, Transform};
+use ray_tracing_utils::hittable::{Hittable, HittableList, Instance, Sphere};
+use ray_tracing_utils::material::Lambertian;
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn composes_in_application_order() {
+    let t = Transform::scale(Vec3::new(2.0, 2.0, 2.0))
+        .then(Transform::rotate_y(FRAC_PI_2))
+        .then(Transform::translate(Vec3::new(0.0, 1.0, 0.0)));
+    // (1, 0, 0) -> (2, 0, 0) -> (0, 0, -2) -> (0, 1, -2)
+    assert_close(t.transform_point(Point3::new(1.0, 0.0, 0.0)), Point3::new(0.0, 1.0, -2.0));
+    assert_close(t.transform_vector(Vec3::new(1.0, 0.0, 0.0)), Vec3::new(0.0, 0.0, -2.0));
+
+    let inverse = t.inverse();
+    let p = Point3::new(0.3, -1.2, 4.5);
+    assert_close(inverse.transform_point(t.transform_point(p)), p);
+}
+
+#[test]
+fn general_matrix_inverse() {
+    let t = Transform::from_matrix([
+        [2.0, 1.0, 0.0, 3.0],
+        [0.0, 1.0, 4.0, -1.0],
+        [1.0, 0.0, 1.0, 2.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]).unwrap();
+    let p = Point3::new(-0.7, 2.0, 1.5);
+    assert_close(t.inverse().transform_point(t.transform_point(p)), p);
+
+    let columns = [
+        [2.0, 0.0, 1.0, 0.0],
+        [1.0, 1.0, 0.0, 0.0],
+        [0.0, 4.0, 1.0, 0.0],
+        [3.0, -1.0, 2.0, 1.0],
+    ];
+    assert_eq!(Transform::from_columns(columns).unwrap(), t);
+
+    let mut singular = *Transform::identity().matrix();
+    singular[1][1] = 0.0;
+    assert!(Transform::from_matrix(singular).is_none());
+}
+
+#[test]
+fn normals_stay_perpendicular_under_non_uniform_scale() {
+    let t = Transform::scale(Vec3::new(4.0, 1.0, 1.0));
+    // Surface x + y = 0, spanned by (1, -1, 0) and the z axis.
+    let tangent = t.transform_vector(Vec3::new(1.0, -1.0, 0.0));
+    let normal = t.transform_normal(Vec3::new(1.0, 1.0, 0.0));
+    assert!(Vec3::dot(tangent, normal).abs() < 1e-6);
+    assert!(Transform::scale(Vec3::new(-1.0, 1.0, 1.0)).determinant() < 0.0);
+}
+
+fn unit_sphere() -> Arc<dyn Hittable> {
+    Arc::new(Sphere {
+        center: Point3::new(0.0, 0.0, 0.0),
+        radius: 1.0,
+        material: Box::new(Lambertian::default()),
+    })
+}
+
+#[test]
+fn instance_hits_transformed_object() {
+    let sphere = unit_sphere();
+    let transform = Transform::scale(Vec3::new(1.0, 2.0, 1.0)).then(Transform::translate(Vec3::new(5.0, 0.0, 0.0)));
+    let instance = Instance::new(sphere, transform);
+
+    let bbox = instance.bounding_box().unwrap();
+    assert_close(bbox.min, Point3::new(4.0, -2.0, -1.0));
+    assert_close(bbox.max, Point3::new(6.0, 2.0, 1.0));
+
+    // Straight down onto the stretched top.
+    let ray = Ray::new(Point3::new(5.0, 10.0, 0.0), Vec3::new(0.0, -2.0, 0.0));
+    let rec = instance.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!((rec.t - 4.0).abs() < 1e-4);
+    assert_close(rec.p, Point3::new(5.0, 2.0, 0.0));
+    assert_close(rec.normal, Vec3::new(0.0, 1.0, 0.0));
+    assert!(rec.front_face);
+
+    // Off the side of the stretched ellipsoid, where the sphere is wider
+    // than the instance.
+    let side = Ray::new(Point3::new(6.0, 1.9, 10.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(instance.hit(&side, 0.001, f32::INFINITY).is_none());
+}
+
+#[test]
+fn instances_share_one_object() {
+    let sphere = unit_sphere();
+    let hittables: Vec<Box<dyn Hittable>> = (0..100).map(|i| {
+        let offset = Vec3::new(3.0 * i as f32, 0.0, 0.0);
+        Box::new(Instance::new(sphere.clone(), Transform::translate(offset))) as Box<dyn Hittable>
+    }).collect();
+    assert_eq!(Arc::strong_count(&sphere), 101);
+
+    let world = HittableList { hittables };
+    let ray = Ray::new(Point3::new(150.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = world.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert_close(rec.p, Point3::new(150.0, 0.0, 1.0));
+    assert_close(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+}
@@ -0,0 +1,110 @@
+use ray_tracing_utils::bvh::BvhNode;
+use ray_tracing_utils::hittable::{Hittable, HittableList, Triangle};
+use ray_tracing_utils::material::Lambertian;
+use ray_tracing_utils::math::{Point3, Ray, Vec3};
+
+fn unit_triangle() -> Triangle {
+    // Counter-clockwise seen from +z.
+    Triangle::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Box::new(Lambertian::default()),
+    )
+}
+
+fn approx(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-5
+}
+
+#[test]
+fn hit_and_barycentrics() {
+    let triangle = unit_triangle();
+    let ray = Ray::new(Point3::new(0.25, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = triangle.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!(approx(rec.t, 2.0));
+    assert_eq!(rec.p, Point3::new(0.25, 0.5, 0.0));
+    // Without explicit UVs the weights of the second and third vertex are used.
+    assert!(approx(rec.u, 0.25) && approx(rec.v, 0.5));
+    assert!(rec.front_face);
+    assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+
+    assert!(triangle.hit(&ray, 0.001, 1.5).is_none());
+    let outside = Ray::new(Point3::new(0.75, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(triangle.hit(&outside, 0.001, f32::INFINITY).is_none());
+    let parallel = Ray::new(Point3::new(-1.0, 0.2, 0.0), Vec3::new(1.0, 0.0, 0.0));
+    assert!(triangle.hit(&parallel, 0.001, f32::INFINITY).is_none());
+}
+
+#[test]
+fn back_face() {
+    let triangle = unit_triangle();
+    let ray = Ray::new(Point3::new(0.25, 0.25, -1.0), Vec3::new(0.0, 0.0, 1.0));
+    let rec = triangle.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!(!rec.front_face);
+    assert_eq!(rec.normal, Vec3::new(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn interpolated_normals_and_uvs() {
+    let tilt = Vec3::new(1.0, 0.0, 1.0).normalized();
+    let triangle = unit_triangle()
+        .with_normals([Vec3::new(0.0, 0.0, 1.0), tilt, Vec3::new(0.0, 0.0, 1.0)])
+        .with_uvs([(0.0, 0.0), (1.0, 0.0), (0.5, 1.0)]);
+
+    let ray = Ray::new(Point3::new(0.5, 0.25, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = triangle.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!(approx(rec.u, 0.5 + 0.25 * 0.5) && approx(rec.v, 0.25));
+    assert!(approx(rec.normal.length(), 1.0));
+    assert!(rec.normal.x > 0.0 && rec.normal.z > 0.0);
+
+    // From behind, the interpolated normal is flipped toward the ray.
+    let ray = Ray::new(Point3::new(0.5, 0.25, -1.0), Vec3::new(0.0, 0.0, 1.0));
+    let rec = triangle.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!(!rec.front_face);
+    assert!(rec.normal.z < 0.0);
+
+}
+
+#[test]
+fn vertex_normals_decide_the_front_of_clockwise_triangles() {
+    // Clockwise seen from +z, but its normals say +z is outside.
+    let triangle = Triangle::new(
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Box::new(Lambertian::default()),
+    )
+    .with_normals([Vec3::new(0.0, 0.0, 1.0); 3]);
+
+    let ray = Ray::new(Point3::new(0.2, 0.2, 1.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = triangle.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!(rec.front_face);
+    assert_eq!(rec.normal, Vec3::new(0.0, 0.0, 1.0));
+
+    let ray = Ray::new(Point3::new(0.2, 0.2, -1.0), Vec3::new(0.0, 0.0, 1.0));
+    let rec = triangle.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!(!rec.front_face);
+    assert_eq!(rec.normal, Vec3::new(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn axis_aligned_triangles_in_bvh() {
+    let mut list = HittableList::default();
+    for i in 0..20 {
+        let z = -(i as f32);
+        list.hittables.push(Box::new(Triangle::new(
+            Point3::new(-1.0, -1.0, z),
+            Point3::new(1.0, -1.0, z),
+            Point3::new(0.0, 1.0, z),
+            Box::new(Lambertian::default()),
+        )));
+    }
+    let bvh = BvhNode::new(list);
+    let bbox = bvh.bounding_box().unwrap();
+    assert_eq!(bbox.min, Point3::new(-1.0, -1.0, -19.0));
+
+    let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+    let rec = bvh.hit(&ray, 0.001, f32::INFINITY).unwrap();
+    assert!(approx(rec.t, 5.0));
+}
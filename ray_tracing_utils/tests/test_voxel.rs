@@ -0,0 +1,97 @@
+use ray_tracing_utils::math::Point3;
+use ray_tracing_utils::aabb::Aabb;
+use ray_tracing_utils::voxel::{self, VoxelGrid, VoxelError};
+
+fn header(encoding: i32, resolution: [i32; 3], channels: i32) -> Vec<u8> {
+    let mut data = b"VOL\x03".to_vec();
+    for v in [encoding, resolution[0], resolution[1], resolution[2], channels] {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    for v in [0.0f32, 0.0, 0.0, 2.0, 1.0, 1.0] {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    data
+}
+
+fn unit_bounds() -> Aabb {
+    Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 1.0, 1.0))
+}
+
+#[test]
+fn float_grid_round_trips() {
+    let grid = VoxelGrid::new([2, 3, 1], unit_bounds(), vec![0.0, 1.0, 2.0, 3.0, 4.5, 0.25]);
+    let mut bytes = vec![];
+    voxel::write(&grid, &mut bytes).unwrap();
+    assert_eq!(bytes.len(), 48 + 6 * 4);
+
+    let decoded = voxel::read(bytes.as_slice()).unwrap();
+    assert_eq!(decoded, grid);
+    assert_eq!(decoded.get(1, 2, 0), 0.25);
+    assert_eq!(decoded.get(0, 1, 0), 2.0);
+    assert_eq!(decoded.max_value(), 4.5);
+}
+
+#[test]
+fn byte_grid_is_normalized() {
+    let mut data = header(3, [2, 1, 2], 1);
+    data.extend_from_slice(&[0, 255, 51, 102]);
+    let grid = voxel::decode(&data).unwrap();
+    assert_eq!(grid.resolution(), [2, 1, 2]);
+    assert_eq!(grid.bounds(), unit_bounds());
+    assert_eq!(grid.data(), &[0.0, 1.0, 0.2, 0.4]);
+}
+
+#[test]
+fn bad_files_are_rejected() {
+    assert!(matches!(voxel::decode(b"VOL\x02rest"), Err(VoxelError::BadMagic)));
+    assert!(matches!(voxel::decode(b"VO"), Err(VoxelError::UnexpectedEof)));
+    assert!(matches!(voxel::decode(&header(2, [1, 1, 1], 1)), Err(VoxelError::UnsupportedEncoding(2))));
+    assert!(matches!(voxel::decode(&header(1, [1, 1, 1], 3)), Err(VoxelError::InvalidHeader(_))));
+    assert!(matches!(voxel::decode(&header(1, [0, 1, 1], 1)), Err(VoxelError::InvalidHeader(_))));
+
+    let mut short = header(1, [2, 2, 2], 1);
+    short.extend_from_slice(&[0; 7 * 4]);
+    assert!(matches!(voxel::decode(&short), Err(VoxelError::UnexpectedEof)));
+}
+
+#[test]
+fn samples_interpolate_between_cell_centers() {
+    // Cell centers at x = 0.5 and 1.5.
+    let grid = VoxelGrid::new([2, 1, 1], unit_bounds(), vec![1.0, 3.0]);
+    let at = |x: f32| grid.sample(Point3::new(x, 0.5, 0.5));
+    assert_eq!(at(0.5), 1.0);
+    assert_eq!(at(1.0), 2.0);
+    assert_eq!(at(1.25), 2.5);
+    // Constant over the outer half cells, empty outside.
+    assert_eq!(at(0.1), 1.0);
+    assert_eq!(at(1.9), 3.0);
+    assert_eq!(at(2.1), 0.0);
+    assert_eq!(grid.sample(Point3::new(1.0, -0.1, 0.5)), 0.0);
+}
+
+#[test]
+fn from_fn_visits_cell_centers() {
+    let grid = VoxelGrid::from_fn([2, 1, 1], unit_bounds(), |p| p.x);
+    assert_eq!(grid.data(), &[0.5, 1.5]);
+}
+
+#[test]
+fn non_finite_and_negative_samples_are_rejected() {
+    for bad in [f32::INFINITY, f32::NAN, -0.5] {
+        let mut data = header(1, [2, 1, 1], 1);
+        data.extend_from_slice(&1.0f32.to_le_bytes());
+        data.extend_from_slice(&bad.to_le_bytes());
+        match voxel::decode(&data) {
+            Err(VoxelError::InvalidSample { index: 1, value }) => {
+                assert!(value.is_nan() == bad.is_nan() && (bad.is_nan() || value == bad));
+            },
+            other => panic!("{} gave {:?}", bad, other),
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "finite, non-negative")]
+fn grids_refuse_infinite_samples() {
+    VoxelGrid::new([1, 1, 1], unit_bounds(), vec![f32::INFINITY]);
+}
@@ -0,0 +1,254 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use ray_tracing_utils::scene::{self, Scene};
+use ray_tracing_utils::render::Renderer;
+use ray_tracing_utils::framebuffer::{Framebuffer, ResolveSettings};
+use ray_tracing_utils::pnm::PpmEncoding;
+
+const USAGE: &str = "\
+Usage: render [OPTIONS] <SCENE> <OUTPUT>
+
+Arguments:
+  <SCENE>   scene file (.toml, .json, .gltf or .glb) or built-in scene name
+  <OUTPUT>  image to write; .png, .ppm or .hdr
+
+Options:
+  -W, --width <N>     image width; keeps the scene's aspect ratio if alone
+  -H, --height <N>    image height; keeps the scene's aspect ratio if alone
+  -s, --spp <N>       samples per pixel
+  -d, --depth <N>     maximum bounces per path
+      --seed <N>      seed for random built-in scenes and for sampling, so renders
+                      are reproducible [default: 0]
+  -t, --threads <N>   worker threads [default: all cores]
+      --list          print the built-in scene names and exit
+  -h, --help          print this help and exit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Png,
+    Ppm,
+    Hdr,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "png" => Some(Format::Png),
+            "ppm" => Some(Format::Ppm),
+            "hdr" => Some(Format::Hdr),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Options {
+    scene: String,
+    output: PathBuf,
+    width: Option<u32>,
+    height: Option<u32>,
+    samples_per_pixel: Option<u32>,
+    max_depth: Option<u32>,
+    seed: u64,
+    threads: Option<usize>,
+}
+
+enum Command {
+    Render(Options),
+    List,
+    Help,
+}
+
+/// Why the program stopped: bad arguments exit with 2, everything else
+/// with 1.
+enum Failure {
+    Usage(String),
+    Runtime(String),
+}
+
+fn main() {
+    let code = match run(env::args().skip(1).collect()) {
+        Ok(()) => 0,
+        Err(Failure::Usage(message)) => {
+            eprintln!("error: {}\n\nRun `render --help` for usage.", message);
+            2
+        }
+        Err(Failure::Runtime(message)) => {
+            eprintln!("error: {}", message);
+            1
+        }
+    };
+    process::exit(code);
+}
+
+fn run(args: Vec<String>) -> Result<(), Failure> {
+    let options = match parse_args(args).map_err(Failure::Usage)? {
+        Command::Help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Command::List => {
+            for name in scene::builtin::NAMES {
+                println!("{}", name);
+            }
+            return Ok(());
+        }
+        Command::Render(options) => options,
+    };
+
+    let format = Format::from_path(&options.output).ok_or_else(|| Failure::Usage(format!(
+        "can't tell the image format of '{}'; use a .png, .ppm or .hdr extension",
+        options.output.display(),
+    )))?;
+
+    let mut scene = load_scene(&options.scene, options.seed)?;
+    apply_overrides(&mut scene, &options);
+
+    let renderer = {
+        let mut renderer = Renderer::new(scene.settings.clone());
+        renderer.seed = Some(options.seed);
+        if let Some(threads) = options.threads {
+            renderer.tiles.threads = threads;
+        }
+        renderer
+    };
+
+    let settings = &scene.settings;
+    let pb = ProgressBar::new(settings.width as u64 * settings.height as u64);
+    pb.set_style(
+        ProgressStyle::with_template(
+            "[{elapsed_precise}] [{bar:40.cyan/blue}] ({pos}/{len})"
+        ).unwrap()
+    );
+    let framebuffer = renderer.render_with_progress(&scene.camera, scene.world.as_ref(), |tile| {
+        pb.inc(tile.width as u64 * tile.height as u64)
+    });
+    pb.finish_and_clear();
+
+    save(&framebuffer, &options.output, format).map_err(|e| Failure::Runtime(format!(
+        "couldn't write {}: {}", options.output.display(), e,
+    )))
+}
+
+fn parse_args(args: Vec<String>) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut positional = vec![];
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            positional.push(arg);
+            continue;
+        }
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || inline.clone().or_else(|| args.next())
+            .ok_or_else(|| format!("{} needs a value", flag));
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--list" => return Ok(Command::List),
+            "-W" | "--width" => options.width = Some(positive(&flag, &value()?)?),
+            "-H" | "--height" => options.height = Some(positive(&flag, &value()?)?),
+            "-s" | "--spp" => options.samples_per_pixel = Some(positive(&flag, &value()?)?),
+            "-d" | "--depth" => options.max_depth = Some(positive(&flag, &value()?)?),
+            "-t" | "--threads" => options.threads = Some(positive::<usize>(&flag, &value()?)?),
+            "--seed" => {
+                let value = value()?;
+                options.seed = value.parse()
+                    .map_err(|_| format!("{} expects a non-negative integer, got '{}'", flag, value))?;
+            }
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    match <[String; 2]>::try_from(positional) {
+        Ok([scene, output]) => {
+            options.scene = scene;
+            options.output = PathBuf::from(output);
+            Ok(Command::Render(options))
+        }
+        Err(positional) if positional.len() < 2 => Err("expected a scene and an output path".to_string()),
+        Err(positional) => Err(format!("unexpected argument '{}'", positional[2])),
+    }
+}
+
+fn positive<T>(flag: &str, value: &str) -> Result<T, String>
+where
+    T: std::str::FromStr + Default + PartialEq,
+{
+    match value.parse::<T>() {
+        Ok(n) if n != T::default() => Ok(n),
+        _ => Err(format!("{} expects a positive integer, got '{}'", flag, value)),
+    }
+}
+
+/// Reads a scene file if `name` looks like one, and otherwise picks the
+/// built-in scene of that name.
+fn load_scene(name: &str, seed: u64) -> Result<Scene, Failure> {
+    let path = Path::new(name);
+    let ext = path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase);
+    let result = match ext.as_deref() {
+        Some("gltf" | "glb") => scene::gltf::load(path).map_err(|e| e.to_string()),
+        Some("toml" | "json") => scene::file::load(path).map_err(|e| e.to_string()),
+        Some(_) if path.exists() => {
+            return Err(Failure::Usage(format!(
+                "can't tell the scene format of '{}'; use .toml, .json, .gltf or .glb",
+                name,
+            )));
+        }
+        _ => {
+            return scene::builtin::by_name(name, seed).ok_or_else(|| Failure::Usage(format!(
+                "no scene file or built-in scene called '{}'; built-in scenes are: {}",
+                name,
+                scene::builtin::NAMES.join(", "),
+            )));
+        }
+    };
+    result.map_err(|message| Failure::Runtime(format!("couldn't load {}: {}", name, message)))
+}
+
+/// Applies the command line settings on top of the scene's own. With only
+/// one of width and height given, the other follows the scene's aspect
+/// ratio; with both, the camera is widened or narrowed to match.
+fn apply_overrides(scene: &mut Scene, options: &Options) {
+    let settings = &mut scene.settings;
+    let aspect_ratio = settings.aspect_ratio();
+    match (options.width, options.height) {
+        (Some(width), Some(height)) => {
+            settings.width = width;
+            settings.height = height;
+        }
+        (Some(width), None) => {
+            settings.width = width;
+            settings.height = ((width as f32 / aspect_ratio).round() as u32).max(1);
+        }
+        (None, Some(height)) => {
+            settings.height = height;
+            settings.width = ((height as f32 * aspect_ratio).round() as u32).max(1);
+        }
+        (None, None) => {}
+    }
+    scene.camera.set_aspect_ratio(settings.aspect_ratio());
+
+    if let Some(spp) = options.samples_per_pixel {
+        settings.samples_per_pixel = spp;
+    }
+    if let Some(depth) = options.max_depth {
+        settings.max_depth = depth;
+    }
+}
+
+fn save(framebuffer: &Framebuffer, path: &Path, format: Format) -> std::io::Result<()> {
+    match format {
+        Format::Png => framebuffer.resolve(&ResolveSettings::default()).save_png(path),
+        Format::Ppm => framebuffer.resolve(&ResolveSettings::default()).save_ppm(path, PpmEncoding::Binary),
+        Format::Hdr => framebuffer.to_hdr().save_hdr(path),
+    }
+}
@@ -0,0 +1,120 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use ray_tracing_utils::image::Image;
+use ray_tracing_utils::hdr::HdrImage;
+
+fn render(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_render"))
+        .args(args)
+        .output()
+        .expect("failed to run render")
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("render_cli_{}_{}", std::process::id(), name))
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn renders_builtin_scene_to_ppm() {
+    let path = temp_path("random.ppm");
+    let output = render(&[
+        "random", path.to_str().unwrap(),
+        "--width", "8", "--height", "6", "--spp", "1", "--depth", "2", "--threads", "1",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let image = Image::from_path(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!((image.width, image.height), (8, 6));
+}
+
+#[test]
+fn width_alone_keeps_aspect_ratio() {
+    let path = temp_path("aspect.hdr");
+    let output = render(&[
+        "random", path.to_str().unwrap(), "-W", "32", "-s", "1", "-d", "1", "--seed=7",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let image = HdrImage::from_path(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    // The random scene is 384 x 216.
+    assert_eq!((image.width, image.height), (32, 18));
+}
+
+#[test]
+fn same_seed_renders_the_same_image() {
+    let render_bytes = |name: &str, seed: &str, threads: &str| {
+        let path = temp_path(name);
+        let output = render(&[
+            "random", path.to_str().unwrap(), "-W", "16", "-s", "2", "-d", "3", "--seed", seed, "-t", threads,
+        ]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        bytes
+    };
+
+    let image = render_bytes("seed_a.ppm", "5", "1");
+    assert_eq!(image, render_bytes("seed_b.ppm", "5", "2"));
+    assert_ne!(image, render_bytes("seed_c.ppm", "6", "1"));
+}
+
+#[test]
+fn renders_scene_file_to_png() {
+    let scene = concat!(env!("CARGO_MANIFEST_DIR"), "/../scenes/three_spheres.toml");
+    let path = temp_path("three_spheres.png");
+    let output = render(&[scene, path.to_str().unwrap(), "-W", "8", "-H", "8", "-s", "1", "-d", "2"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let data = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(data.starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn help_and_list_succeed() {
+    let output = render(&["--help"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Usage: render"));
+
+    let output = render(&["--list"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).lines().any(|line| line == "random"));
+}
+
+#[test]
+fn bad_arguments_exit_with_usage_error() {
+    let cases: &[(&[&str], &str)] = &[
+        (&[], "expected a scene and an output path"),
+        (&["random"], "expected a scene and an output path"),
+        (&["random", "out.png", "extra"], "unexpected argument 'extra'"),
+        (&["random", "out.jpg"], "image format of 'out.jpg'"),
+        (&["nonexistent", "out.png"], "no scene file or built-in scene called 'nonexistent'"),
+        (&["random", "out.png", "--spp", "0"], "--spp expects a positive integer, got '0'"),
+        (&["random", "out.png", "--width", "wide"], "--width expects a positive integer"),
+        (&["random", "out.png", "--seed", "-1"], "--seed expects a non-negative integer"),
+        (&["random", "out.png", "--depth"], "--depth needs a value"),
+        (&["random", "out.png", "--frobnicate"], "unknown option '--frobnicate'"),
+    ];
+    for (args, message) in cases {
+        let output = render(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(stderr(&output).contains(message), "{:?}: {}", args, stderr(&output));
+    }
+}
+
+#[test]
+fn unreadable_scene_file_exits_with_failure() {
+    let scene = temp_path("missing.toml");
+    let output = render(&[scene.to_str().unwrap(), "out.png"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("couldn't load"), "{}", stderr(&output));
+}
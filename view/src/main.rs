@@ -1,4 +1,5 @@
 use std::env;
+use std::process;
 
 use log::error;
 use pixels::{Error, Pixels, SurfaceTexture};
@@ -22,7 +23,10 @@ fn main() -> Result<(), Error> {
     }
 
     let filepath = &args[1];
-    let img = Image::from_path(filepath);
+    let img = Image::from_path(filepath).unwrap_or_else(|e| {
+        eprintln!("Couldn't read {}: {}", filepath, e);
+        process::exit(1);
+    });
 
     let event_loop = EventLoop::new();
     let mut input = WinitInputHelper::new();
@@ -58,18 +62,16 @@ fn main() -> Result<(), Error> {
                     return;
                 }
             },
-            Event::WindowEvent { ref event, .. } => {
-                match event {
-                    // WindowEvent::CloseRequested => control_flow.set_exit(),
-                    WindowEvent::MouseInput {
-                        state: ElementState::Pressed,
-                        button: MouseButton::Left,
-                        ..
-                    } => {
-                        window.drag_window().unwrap()
-                    }
-                    _ => (),
-                }
+            // Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => control_flow.set_exit(),
+            Event::WindowEvent {
+                event: WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Left,
+                    ..
+                },
+                ..
+            } => {
+                window.drag_window().unwrap()
             },
             _ => (),
         };